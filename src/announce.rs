@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{net, render};
+
+/// tgit.toml 中的 `[announce]`，发布后通知的 incoming webhook。
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Webhooks {
+    pub slack: Option<String>,
+    pub discord: Option<String>,
+    pub teams: Option<String>,
+}
+
+// Discord 的消息最长 2000 个字符
+const DISCORD_LIMIT: usize = 2000;
+
+/// 把 changelog 转换为各个平台的格式并发送，返回失败的平台和原因。
+/// 发布已经完成，通知失败不应该使命令失败，由调用者决定如何提示。
+pub fn announce(webhooks: &Webhooks, title: &str, changelog: &str) -> Vec<(String, String)> {
+    let targets = [
+        ("slack", webhooks.slack.as_deref()),
+        ("discord", webhooks.discord.as_deref()),
+        ("teams", webhooks.teams.as_deref()),
+    ];
+    let mut failed = Vec::<(String, String)>::new();
+    for (platform, url) in targets {
+        let url = match url {
+            Some(url) => url,
+            None => continue,
+        };
+        if let Err(err) = post(url, payload(platform, title, changelog).to_string()) {
+            failed.push((platform.to_string(), err.to_string()));
+        }
+    }
+    failed
+}
+
+pub fn payload(platform: &str, title: &str, changelog: &str) -> serde_json::Value {
+    match platform {
+        "slack" => serde_json::json!({
+            "text": format!("*{}*\n{}", title, slack_markup(changelog)),
+        }),
+        "discord" => {
+            let mut content = format!("**{}**\n{}", title, changelog.trim());
+            if content.chars().count() > DISCORD_LIMIT {
+                content = content.chars().take(DISCORD_LIMIT - 1).collect::<String>();
+                content.push('…');
+            }
+            serde_json::json!({ "content": content })
+        }
+        // Teams 的 MessageCard 支持部分 HTML
+        _ => serde_json::json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": title,
+            "title": title,
+            "text": render::render(changelog, render::Format::Html),
+        }),
+    }
+}
+
+fn post(url: &str, body: String) -> Result<(), Box<dyn std::error::Error>> {
+    let request = net::client()?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body);
+    let response = net::send(request)?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status().as_u16()).into());
+    }
+    Ok(())
+}
+
+// Slack 的 mrkdwn：链接为 <url|text>，粗体为单个 *，没有标题
+pub fn slack_markup(markdown: &str) -> String {
+    let link_re = regex!(r"\[([^\]]*)\]\(([^)\s]+)\)");
+    let bold_re = regex!(r"\*\*([^*]+)\*\*");
+    let strike_re = regex!(r"~~([^~]+)~~");
+    let mut lines = Vec::<String>::new();
+    for line in markdown.trim().lines() {
+        if line.trim().starts_with("<!--") {
+            continue;
+        }
+        let line = match line
+            .strip_prefix("### ")
+            .or_else(|| line.strip_prefix("## "))
+        {
+            Some(title) => format!("**{}**", title),
+            None => line.to_string(),
+        };
+        let line = line
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        let line = link_re.replace_all(&line, "<$2|$1>");
+        let line = bold_re.replace_all(&line, "*$1*");
+        let line = strike_re.replace_all(&line, "~$1~");
+        lines.push(line.to_string());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announce_payload() {
+        let changelog = "## v0.2.0\n\n### :bug: Bug Fixes\n\n- fix **a** ([1234567](https://x/commit/1234567))\n\n- a <a@example.com>\n";
+        assert_eq!(
+            slack_markup(changelog),
+            "*v0.2.0*\n\n*:bug: Bug Fixes*\n\n- fix *a* (<https://x/commit/1234567|1234567>)\n\n- a &lt;a@example.com&gt;"
+        );
+        let discord = payload("discord", "tgit v0.2.0", "- x\n".repeat(1000).as_str());
+        assert_eq!(discord["content"].as_str().unwrap().chars().count(), 2000);
+        let teams = payload("teams", "tgit v0.2.0", changelog);
+        assert!(teams["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("<h2>v0.2.0</h2>"));
+    }
+}
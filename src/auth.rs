@@ -0,0 +1,123 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, IsTerminal},
+    sync::{Mutex, OnceLock},
+};
+
+use inquire::{Password, PasswordDisplayMode};
+
+use crate::forge;
+
+// keychain 中的 service，account 是 host
+const SERVICE: &str = "tgit";
+
+// 每个 host 只读取一次 keychain
+static TOKENS: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+
+/// `tgit auth login` 的参数对应的 host，可以使用 forge 的名字代替 host。
+pub fn host(forge: &str) -> String {
+    let host = forge
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    match host {
+        "github" => "github.com".to_string(),
+        "gitlab" => "gitlab.com".to_string(),
+        "codeberg" => "codeberg.org".to_string(),
+        _ => host.to_string(),
+    }
+}
+
+fn entry(host: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, host)
+}
+
+/// keychain 中 remote host 的 token。配置了 web 的 host（例如 SSH 别名）使用 web 的 host。
+pub fn stored_token(host: &str) -> Option<String> {
+    let host = forge::gh_host(host);
+    let tokens = TOKENS.get_or_init(Default::default);
+    if let Some(token) = tokens.lock().unwrap().get(host.as_str()) {
+        return token.clone();
+    }
+    let token = match entry(host.as_str()).and_then(|entry| entry.get_password()) {
+        Ok(token) => Some(token),
+        Err(keyring::Error::NoEntry) => None,
+        Err(err) => {
+            log::debug!(
+                "failed to read the token of {} from the keychain: {}",
+                host,
+                err
+            );
+            None
+        }
+    };
+    tokens.lock().unwrap().insert(host, token.clone());
+    token
+}
+
+/// vars 中第一个设置了的环境变量，都没有设置时使用 keychain 中的 token。
+pub fn token(host: &str, vars: &[&str]) -> Option<String> {
+    vars.iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| stored_token(host))
+}
+
+/// `tgit auth login`，token 从终端输入或者从标准输入读取，不会出现在 shell 历史中。
+pub fn login(forge: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let host = host(forge);
+    let token = if std::io::stdin().is_terminal() {
+        Password::new(format!("Token for {}:", host).as_str())
+            .with_display_mode(PasswordDisplayMode::Masked)
+            .without_confirmation()
+            .prompt()?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line
+    };
+    let token = token.trim();
+    if token.is_empty() {
+        return Err("The token is empty.".into());
+    }
+    entry(host.as_str())
+        .and_then(|entry| entry.set_password(token))
+        .map_err(|err| format!("Failed to store the token in the keychain: {}", err))?;
+    log::info!("Stored the token of {} in the keychain.", host);
+    Ok(())
+}
+
+/// `tgit auth logout`
+pub fn logout(forge: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let host = host(forge);
+    match entry(host.as_str()).and_then(|entry| entry.delete_credential()) {
+        Ok(()) => log::info!("Removed the token of {} from the keychain.", host),
+        Err(keyring::Error::NoEntry) => log::info!("No token of {} is stored.", host),
+        Err(err) => {
+            return Err(format!("Failed to remove the token from the keychain: {}", err).into())
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_host() {
+        assert_eq!(host("github"), "github.com");
+        assert_eq!(host("codeberg"), "codeberg.org");
+        assert_eq!(host("https://git.mycorp.com/"), "git.mycorp.com");
+        // 环境变量优先于 keychain
+        std::env::set_var("TGIT_TEST_AUTH_TOKEN", "from-env");
+        assert_eq!(
+            token(
+                "gitlab.com",
+                &["TGIT_TEST_UNSET_TOKEN", "TGIT_TEST_AUTH_TOKEN"]
+            )
+            .as_deref(),
+            Some("from-env")
+        );
+    }
+}
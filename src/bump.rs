@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use crate::{compile_globs, config::Config, Commit, Glob};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+
+    /// 1.0 之前的版本：breaking change 只升级 minor，feat 只升级 patch。
+    pub fn pre_major(self) -> Bump {
+        match self {
+            Bump::Major => Bump::Minor,
+            Bump::Minor => Bump::Patch,
+            bump => bump,
+        }
+    }
+}
+
+/// 分析一个版本中的变更。
+pub struct BumpInput<'a> {
+    pub commit_map: &'a HashMap<String, Vec<Commit>>,
+    pub has_breaking: bool,
+    /// from 和 to 之间修改过的文件。
+    pub paths: &'a [String],
+}
+
+/// 根据变更推断下一个版本需要的 bump。
+pub trait BumpAnalyzer {
+    fn analyze(&self, input: &BumpInput) -> Bump;
+}
+
+/// 默认的规则：breaking change 为 major，feat 为 minor，其他为 patch。
+pub struct Conventional;
+
+impl BumpAnalyzer for Conventional {
+    fn analyze(&self, input: &BumpInput) -> Bump {
+        if input.has_breaking {
+            Bump::Major
+        } else if input.commit_map.contains_key("feat") {
+            Bump::Minor
+        } else {
+            Bump::Patch
+        }
+    }
+}
+
+/// 根据 commit 或 PR 的 label 推断，没有相关 label 时不发布。
+pub struct Labels;
+
+impl BumpAnalyzer for Labels {
+    fn analyze(&self, input: &BumpInput) -> Bump {
+        let mut bump = Bump::None;
+        for commit in input.commit_map.values().flatten() {
+            for badge in &commit.badges {
+                let label_bump = match badge.as_str() {
+                    "major" | "breaking" => Bump::Major,
+                    "minor" | "feature" | "enhancement" => Bump::Minor,
+                    "patch" | "bug" | "fix" => Bump::Patch,
+                    _ => Bump::None,
+                };
+                bump = bump.max(label_bump);
+            }
+        }
+        bump
+    }
+}
+
+/// 只修改了 ignore 中的文件（例如文档）时不发布，否则使用默认的规则。
+pub struct Paths {
+    ignore: Vec<Glob>,
+}
+
+impl BumpAnalyzer for Paths {
+    fn analyze(&self, input: &BumpInput) -> Bump {
+        let ignored = input
+            .paths
+            .iter()
+            .all(|path| self.ignore.iter().any(|glob| glob.is_match(path)));
+        if ignored {
+            return Bump::None;
+        }
+        Conventional.analyze(input)
+    }
+}
+
+/// tgit.toml 中 `bump` 选择的 analyzer，默认为 `conventional`。
+pub fn get_analyzer(config: &Config) -> Result<Box<dyn BumpAnalyzer>, Box<dyn std::error::Error>> {
+    match config.bump.as_deref().unwrap_or("conventional") {
+        "conventional" => Ok(Box::new(Conventional)),
+        "labels" => Ok(Box::new(Labels)),
+        "paths" => Ok(Box::new(Paths {
+            ignore: compile_globs(
+                &config
+                    .no_release_paths
+                    .clone()
+                    .unwrap_or(vec!["docs/*".to_string(), "*.md".to_string()]),
+            )?,
+        })),
+        name => Err(format!(
+            "Unknown bump analyzer `{}`, expected one of: conventional, labels, paths.",
+            name
+        )
+        .into()),
+    }
+}
+
+/// from 和 to 之间修改过的文件，from 为 None 时是 to 中的所有文件。
+pub fn changed_paths(
+    repo: &git2::Repository,
+    from_commit: Option<&git2::Commit>,
+    to_commit: &git2::Commit,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let from_tree = match from_commit {
+        Some(from_commit) => Some(from_commit.tree()?),
+        None => None,
+    };
+    let diff = repo.diff_tree_to_tree(from_tree.as_ref(), Some(&to_commit.tree()?), None)?;
+    let paths = diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_analyzers() {
+        let fix = Commit::new(
+            "0".repeat(40),
+            "fix".to_string(),
+            "".to_string(),
+            "something".to_string(),
+            false,
+            vec![],
+            vec!["enhancement".to_string()],
+        );
+        let commit_map = HashMap::from([("fix".to_string(), vec![fix])]);
+        let docs = vec!["docs/index.md".to_string(), "README.md".to_string()];
+        let input = BumpInput {
+            commit_map: &commit_map,
+            has_breaking: false,
+            paths: &docs,
+        };
+        assert_eq!(Conventional.analyze(&input), Bump::Patch);
+        assert_eq!(Labels.analyze(&input), Bump::Minor);
+        let config = Config {
+            bump: Some("paths".to_string()),
+            ..Default::default()
+        };
+        let analyzer = get_analyzer(&config).unwrap();
+        assert_eq!(analyzer.analyze(&input), Bump::None);
+        let src = vec!["src/main.rs".to_string()];
+        let input = BumpInput {
+            paths: &src,
+            ..input
+        };
+        assert_eq!(analyzer.analyze(&input), Bump::Patch);
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// 缓存的用户名默认的有效天数
+pub const DEFAULT_TTL_DAYS: u64 = 30;
+
+// --no-cache 时为 None，否则为有效的秒数
+static TTL: OnceLock<Option<u64>> = OnceLock::new();
+
+pub fn configure(enabled: bool, ttl_days: Option<u64>) {
+    let ttl = ttl_days.unwrap_or(DEFAULT_TTL_DAYS) * 24 * 60 * 60;
+    let _ = TTL.set(enabled.then_some(ttl));
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    login: String,
+    /// 查询的时间，unix 秒
+    time: u64,
+}
+
+/// 邮箱到用户名的缓存，保存在 `~/.cache/tgit/usernames.json`。GitHub 以外的 forge 的 key 带有 host。
+/// 没有找到用户时 login 为空字符串，同样会被缓存；查询失败时不缓存。
+pub struct UsernameCache {
+    path: Option<PathBuf>,
+    ttl: u64,
+    entries: HashMap<String, Entry>,
+    changed: bool,
+}
+
+impl UsernameCache {
+    pub fn load() -> Self {
+        match TTL
+            .get()
+            .copied()
+            .unwrap_or(Some(DEFAULT_TTL_DAYS * 24 * 60 * 60))
+        {
+            Some(ttl) => Self::load_from(cache_dir().map(|dir| dir.join("usernames.json")), ttl),
+            None => Self::load_from(None, 0),
+        }
+    }
+
+    pub fn load_from(path: Option<PathBuf>, ttl: u64) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(content.as_str()).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            ttl,
+            entries,
+            changed: false,
+        }
+    }
+
+    pub fn get(&self, mail: &str) -> Option<&str> {
+        self.path.as_ref()?;
+        let entry = self.entries.get(mail)?;
+        if now().saturating_sub(entry.time) > self.ttl {
+            return None;
+        }
+        Some(entry.login.as_str())
+    }
+
+    pub fn insert(&mut self, mail: &str, login: &str) {
+        let entry = Entry {
+            login: login.to_string(),
+            time: now(),
+        };
+        self.entries.insert(mail.to_string(), entry);
+        self.changed = true;
+    }
+
+    /// 写回磁盘，同时去掉过期的记录。缓存只是加速，写入失败时忽略。
+    pub fn save(&mut self) {
+        let path = match &self.path {
+            Some(path) if self.changed => path,
+            _ => return,
+        };
+        let now = now();
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, entry| now.saturating_sub(entry.time) <= ttl);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(content) = serde_json::to_string_pretty(&self.entries) {
+            // 多个 tgit 同时运行时不会读到写了一半的缓存
+            let _ = crate::files::write_atomic(path, content);
+        }
+        self.changed = false;
+    }
+}
+
+// $XDG_CACHE_HOME/tgit 或 ~/.cache/tgit
+fn cache_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => crate::files::home_dir()?.join(".cache"),
+    };
+    Some(base.join("tgit"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_username_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tgit").join("usernames.json");
+        let mut cache = UsernameCache::load_from(Some(path.clone()), 60);
+        cache.insert("a@example.com", "a");
+        cache.insert("nobody@example.com", "");
+        cache.save();
+        let cache = UsernameCache::load_from(Some(path.clone()), 60);
+        assert_eq!(cache.get("a@example.com"), Some("a"));
+        assert_eq!(cache.get("nobody@example.com"), Some(""));
+        assert_eq!(cache.get("b@example.com"), None);
+        // 过期的记录不再使用
+        std::fs::write(&path, r#"{"a@example.com": {"login": "a", "time": 0}}"#).unwrap();
+        let cache = UsernameCache::load_from(Some(path), 60);
+        assert_eq!(cache.get("a@example.com"), None);
+    }
+}
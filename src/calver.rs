@@ -0,0 +1,152 @@
+use chrono::{Datelike, NaiveDate};
+
+/// calver.org 中的日期和计数部分。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    /// 2024
+    FullYear,
+    /// 24，2106 年为 106
+    ShortYear,
+    /// 04
+    PaddedYear,
+    Month,
+    PaddedMonth,
+    /// ISO 周
+    Week,
+    PaddedWeek,
+    Day,
+    PaddedDay,
+    /// 同一个日期内的第几次发布，从 0 开始
+    Micro,
+}
+
+/// 日历版本的格式，例如 `YYYY.MM.MICRO` 或 `YY.0M.0D`，各部分以 `.` 分隔。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalVer {
+    parts: Vec<Part>,
+}
+
+impl CalVer {
+    pub fn parse(format: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let parts = format
+            .split('.')
+            .map(|part| match part {
+                "YYYY" => Ok(Part::FullYear),
+                "YY" => Ok(Part::ShortYear),
+                "0Y" => Ok(Part::PaddedYear),
+                "MM" => Ok(Part::Month),
+                "0M" => Ok(Part::PaddedMonth),
+                "WW" => Ok(Part::Week),
+                "0W" => Ok(Part::PaddedWeek),
+                "DD" => Ok(Part::Day),
+                "0D" => Ok(Part::PaddedDay),
+                "MICRO" => Ok(Part::Micro),
+                part => Err(format!(
+                    "Unknown part `{}` in the calver format `{}`, expected YYYY, YY, 0Y, MM, 0M, WW, 0W, DD, 0D or MICRO.",
+                    part, format
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if parts.iter().filter(|part| **part == Part::Micro).count() > 1 {
+            return Err(format!("The calver format `{}` has more than one MICRO.", format).into());
+        }
+        Ok(Self { parts })
+    }
+
+    /// 匹配这个格式的版本号，不包含 `^` 和 `$`。
+    pub fn pattern(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::FullYear => r"\d{4}",
+                Part::PaddedYear | Part::PaddedMonth | Part::PaddedWeek | Part::PaddedDay => {
+                    r"\d{2}"
+                }
+                Part::ShortYear | Part::Month | Part::Week | Part::Day => r"[1-9]\d*",
+                Part::Micro => r"\d+",
+            })
+            .collect::<Vec<_>>()
+            .join(r"\.")
+    }
+
+    /// date 这一天的下一个版本。previous 是上一个版本（不包含 prefix），日期相同时 MICRO 加一，否则从 0 开始。
+    pub fn next(
+        &self,
+        previous: Option<&str>,
+        date: NaiveDate,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let previous = previous
+            .map(|previous| previous.split('.').collect::<Vec<_>>())
+            .filter(|previous| previous.len() == self.parts.len());
+        let same_date = previous.as_ref().is_some_and(|previous| {
+            self.parts
+                .iter()
+                .zip(previous)
+                .filter(|(part, _)| **part != Part::Micro)
+                .all(|(part, value)| format_part(*part, date) == *value)
+        });
+        let micro = match &previous {
+            Some(previous) if same_date => {
+                let index = match self.parts.iter().position(|part| *part == Part::Micro) {
+                    Some(index) => index,
+                    None => {
+                        return Err(format!(
+                            "{} has already been released, add MICRO to the calver format to release more than once per period.",
+                            previous.join(".")
+                        )
+                        .into())
+                    }
+                };
+                previous[index].parse::<u64>()? + 1
+            }
+            _ => 0,
+        };
+        let version = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Micro => micro.to_string(),
+                part => format_part(*part, date),
+            })
+            .collect::<Vec<_>>()
+            .join(".");
+        Ok(version)
+    }
+}
+
+fn format_part(part: Part, date: NaiveDate) -> String {
+    match part {
+        Part::FullYear => date.year().to_string(),
+        Part::ShortYear => (date.year() - 2000).to_string(),
+        Part::PaddedYear => format!("{:02}", date.year() - 2000),
+        Part::Month => date.month().to_string(),
+        Part::PaddedMonth => format!("{:02}", date.month()),
+        Part::Week => date.iso_week().week().to_string(),
+        Part::PaddedWeek => format!("{:02}", date.iso_week().week()),
+        Part::Day => date.day().to_string(),
+        Part::PaddedDay => format!("{:02}", date.day()),
+        Part::Micro => "0".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_calver() {
+        let calver = CalVer::parse("YYYY.0M.MICRO").unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 5, 17).unwrap();
+        assert_eq!(calver.next(None, date).unwrap(), "2024.05.0");
+        assert_eq!(calver.next(Some("2024.05.0"), date).unwrap(), "2024.05.1");
+        assert_eq!(calver.next(Some("2024.04.3"), date).unwrap(), "2024.05.0");
+        let re = Regex::new(format!("^{}$", calver.pattern()).as_str()).unwrap();
+        assert!(re.is_match("2024.05.12"));
+        assert!(!re.is_match("1.2.3"));
+        let daily = CalVer::parse("YY.MM.DD").unwrap();
+        assert_eq!(daily.next(None, date).unwrap(), "24.5.17");
+        assert!(daily.next(Some("24.5.17"), date).is_err());
+        assert!(CalVer::parse("YYYY.PATCH").is_err());
+    }
+}
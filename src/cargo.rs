@@ -0,0 +1,158 @@
+use toml_edit::{DocumentMut, Item, Value};
+
+/// 更新 Cargo.toml 中包自身的版本，保留原来的格式和注释。
+/// `[package].version` 是 `{ workspace = true }` 或者是虚拟 manifest 时更新 `[workspace.package].version`。
+/// 依赖的版本不会被修改。
+pub fn set_version(manifest: &str, version: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut document = manifest
+        .parse::<DocumentMut>()
+        .map_err(|err| format!("Failed to parse Cargo.toml: {}", err))?;
+    let inherits = document
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .is_none_or(|version| {
+            version
+                .get("workspace")
+                .and_then(|workspace| workspace.as_bool())
+                .unwrap_or(false)
+        });
+    // Item 的 get_mut 在 key 不存在时会插入，所以使用 table 的 get_mut
+    let package = if inherits {
+        document
+            .get_mut("workspace")
+            .and_then(|workspace| workspace.as_table_like_mut())
+            .and_then(|workspace| workspace.get_mut("package"))
+    } else {
+        document.get_mut("package")
+    };
+    if let Some(item) = package
+        .and_then(|package| package.as_table_like_mut())
+        .and_then(|package| package.get_mut("version"))
+    {
+        replace_string(item, version);
+    }
+    Ok(document.to_string())
+}
+
+/// 包自身的版本，继承自 workspace 或是虚拟 manifest 时是 `[workspace.package].version`。
+pub fn version(manifest: &str) -> Option<String> {
+    let document = manifest.parse::<DocumentMut>().ok()?;
+    let own = document
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .and_then(|version| version.as_str());
+    own.or_else(|| {
+        document
+            .get("workspace")
+            .and_then(|workspace| workspace.get("package"))
+            .and_then(|package| package.get("version"))
+            .and_then(|version| version.as_str())
+    })
+    .map(|version| version.to_string())
+}
+
+/// manifest 是修改前的 Cargo.toml，更新 Cargo.lock 中对应的包的版本，避免 `--locked` 构建失败。
+/// 版本来自 `[workspace.package]` 时更新所有旧版本的本地包。
+pub fn set_lock_version(
+    lock: &str,
+    manifest: &str,
+    version: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let manifest = manifest
+        .parse::<DocumentMut>()
+        .map_err(|err| format!("Failed to parse Cargo.toml: {}", err))?;
+    let mut document = lock
+        .parse::<DocumentMut>()
+        .map_err(|err| format!("Failed to parse Cargo.lock: {}", err))?;
+    let package = manifest.get("package");
+    let own_version = package
+        .and_then(|package| package.get("version"))
+        .and_then(|version| version.as_str());
+    let (name, old) = match own_version {
+        Some(old) => (
+            package
+                .and_then(|package| package.get("name"))
+                .and_then(|name| name.as_str()),
+            old,
+        ),
+        None => match manifest
+            .get("workspace")
+            .and_then(|workspace| workspace.get("package"))
+            .and_then(|package| package.get("version"))
+            .and_then(|version| version.as_str())
+        {
+            Some(old) => (None, old),
+            None => return Ok(lock.to_string()),
+        },
+    };
+    let entries = match document
+        .get_mut("package")
+        .and_then(|entries| entries.as_array_of_tables_mut())
+    {
+        Some(entries) => entries,
+        None => return Ok(lock.to_string()),
+    };
+    for entry in entries.iter_mut() {
+        // 来自 registry 或 git 的包有 source，本地的包没有
+        let local = !entry.contains_key("source");
+        let matched =
+            name.is_none_or(|name| entry.get("name").and_then(|n| n.as_str()) == Some(name));
+        if local && matched && entry.get("version").and_then(|v| v.as_str()) == Some(old) {
+            if let Some(item) = entry.get_mut("version") {
+                replace_string(item, version);
+            }
+        }
+    }
+    Ok(document.to_string())
+}
+
+// 保留值前后的空白和注释
+fn replace_string(item: &mut Item, new: &str) {
+    if let Some(value) = item.as_value_mut().filter(|value| value.is_str()) {
+        let decor = value.decor().clone();
+        *value = Value::from(new);
+        *value.decor_mut() = decor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo_set_version() {
+        let manifest = "[package]\nname = \"a\"\nversion = \"0.1.0\" # bumped by tgit\n\n[dependencies.serde]\nversion = \"1.0\"\n";
+        assert_eq!(
+            set_version(manifest, "0.2.0").unwrap(),
+            "[package]\nname = \"a\"\nversion = \"0.2.0\" # bumped by tgit\n\n[dependencies.serde]\nversion = \"1.0\"\n"
+        );
+        let inherited = "[workspace.package]\nversion = \"1.0.0\"\n\n[package]\nname = \"a\"\nversion.workspace = true\n";
+        assert_eq!(
+            set_version(inherited, "1.1.0").unwrap(),
+            "[workspace.package]\nversion = \"1.1.0\"\n\n[package]\nname = \"a\"\nversion.workspace = true\n"
+        );
+        let virtual_manifest = "[workspace]\nmembers = [\"a\"]\n\n[workspace.dependencies]\nlog = { version = \"0.4\" }\n";
+        assert_eq!(
+            set_version(virtual_manifest, "2.0.0").unwrap(),
+            virtual_manifest
+        );
+        assert!(set_version("[package", "1.0.0").is_err());
+    }
+
+    #[test]
+    fn test_cargo_set_lock_version() {
+        let lock = "version = 3\n\n[[package]]\nname = \"a\"\nversion = \"0.1.0\"\ndependencies = [\n \"b\",\n]\n\n[[package]]\nname = \"b\"\nversion = \"0.1.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n[[package]]\nname = \"c\"\nversion = \"0.1.0\"\n";
+        let manifest = "[package]\nname = \"a\"\nversion = \"0.1.0\"\n";
+        let bumped = set_lock_version(lock, manifest, "0.2.0").unwrap();
+        assert_eq!(bumped, lock.replacen("0.1.0", "0.2.0", 1));
+        // workspace 的版本更新所有本地的包，registry 的包不变
+        let workspace = "[workspace.package]\nversion = \"0.1.0\"\n";
+        let bumped = set_lock_version(lock, workspace, "0.2.0").unwrap();
+        assert_eq!(bumped.matches("\"0.2.0\"").count(), 2);
+        assert!(bumped.contains("name = \"b\"\nversion = \"0.1.0\""));
+        assert_eq!(
+            set_lock_version(lock, "[workspace]\n", "0.2.0").unwrap(),
+            lock
+        );
+    }
+}
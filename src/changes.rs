@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+
+use inquire::{Confirm, Select, Text};
+use serde::{Deserialize, Serialize};
+
+use crate::{commit, config::Config, Author, Commit};
+
+/// 待发布的变更文件所在的目录，相对于仓库根目录。
+pub const CHANGES_DIR: &str = ".changes";
+
+/// `.changes/` 中的一个 YAML 文件，描述一个待发布的变更。发布时合并到 changelog 中并删除。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub scope: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub breaking: bool,
+    /// `Name <mail>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl Change {
+    /// 作为没有 hash 的 commit 加入 changelog。
+    pub fn to_commit(&self) -> Commit {
+        let authors = self
+            .author
+            .as_deref()
+            .and_then(commit::parse_identity)
+            .map(|(name, mail)| Author {
+                name,
+                mail,
+                username: "".to_string(),
+                commits: 0,
+            })
+            .into_iter()
+            .collect();
+        Commit::new(
+            "".to_string(),
+            self.type_.clone(),
+            self.scope.clone(),
+            self.description.clone(),
+            self.breaking,
+            authors,
+            vec![],
+        )
+    }
+}
+
+/// `tgit change add`，没有指定的字段交互式询问。
+pub fn add(
+    path: &Path,
+    config: &Config,
+    author: Option<&str>,
+    change: Change,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut change = change;
+    if change.type_.is_empty() {
+        change.type_ = Select::new("Type:", config.types()).prompt()?;
+    }
+    if change.description.is_empty() {
+        if change.scope.is_empty() {
+            change.scope = Text::new("Scope (optional):").prompt()?.trim().to_string();
+        }
+        change.description = Text::new("Description:")
+            .with_validator(inquire::required!("The description is required."))
+            .prompt()?
+            .trim()
+            .to_string();
+        if !change.breaking {
+            change.breaking = Confirm::new("Is this a breaking change?")
+                .with_default(false)
+                .prompt()?;
+        }
+    }
+    if change.author.is_none() {
+        change.author = git2::Repository::open(path)
+            .ok()
+            .and_then(|repo| commit::get_signature(&repo, author).ok())
+            .and_then(|signature| Some(format!("{} <{}>", signature.name()?, signature.email()?)));
+    }
+    let dir = path.join(CHANGES_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let file = new_file_name(&dir, &change);
+    std::fs::write(&file, serde_yaml::to_string(&change)?)?;
+    Ok(file)
+}
+
+/// 按文件名排序的待发布变更。
+pub fn load(path: &Path) -> Result<Vec<(PathBuf, Change)>, Box<dyn std::error::Error>> {
+    let dir = path.join(CHANGES_DIR);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = std::fs::read_dir(&dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|file| {
+            matches!(
+                file.extension().and_then(|extension| extension.to_str()),
+                Some("yaml" | "yml")
+            )
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    let mut changes = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(&file)?;
+        let change = serde_yaml::from_str::<Change>(content.as_str())
+            .map_err(|err| format!("Failed to parse {}: {}", file.display(), err))?;
+        changes.push((file, change));
+    }
+    Ok(changes)
+}
+
+/// 发布时删除已经合并到 changelog 中的变更文件，返回删除的文件。
+pub fn remove(path: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut removed = Vec::new();
+    for (file, _) in load(path)? {
+        std::fs::remove_file(&file)?;
+        removed.push(file);
+    }
+    Ok(removed)
+}
+
+// 例如 `feat-support-nested-tables.yaml`，重名时加上序号
+fn new_file_name(dir: &Path, change: &Change) -> PathBuf {
+    let slug = change
+        .description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(6)
+        .collect::<Vec<_>>()
+        .join("-");
+    let stem = format!("{}-{}", change.type_, slug);
+    let mut file = dir.join(format!("{}.yaml", stem));
+    let mut index = 2;
+    while file.exists() {
+        file = dir.join(format!("{}-{}.yaml", stem, index));
+        index += 1;
+    }
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gitt_tests::{fixture, github};
+    use crate::{format_changelog_entry, ChangelogStyle};
+
+    #[test]
+    fn test_changes() {
+        let dir = fixture("with_tag");
+        let change = Change {
+            type_: "feat".to_string(),
+            scope: "parser".to_string(),
+            description: "Support nested tables".to_string(),
+            breaking: false,
+            author: Some("Jannchie <jannchie@gmail.com>".to_string()),
+        };
+        let file = add(dir.path(), &Config::default(), None, change.clone()).unwrap();
+        assert!(file.ends_with(".changes/feat-support-nested-tables.yaml"));
+        let second = add(dir.path(), &Config::default(), None, change.clone()).unwrap();
+        assert!(second.ends_with(".changes/feat-support-nested-tables-2.yaml"));
+        let loaded = load(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].1, change);
+
+        let commit = change.to_commit();
+        assert_eq!(commit.authors[0].name, "Jannchie");
+        let style = ChangelogStyle::default();
+        assert_eq!(
+            format_changelog_entry(&commit, &github("a", "b"), &style, true),
+            "- **parser** Support nested tables - by Jannchie\n"
+        );
+        remove(dir.path()).unwrap();
+        assert!(load(dir.path()).unwrap().is_empty());
+    }
+}
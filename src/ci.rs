@@ -0,0 +1,152 @@
+use serde_json::Value;
+
+use crate::{auth, forge, net};
+
+/// HEAD 上 CI 的状态。
+#[derive(Debug, Default, PartialEq)]
+pub struct Checks {
+    pub failing: Vec<String>,
+    pub pending: Vec<String>,
+}
+
+impl Checks {
+    /// 没有失败和进行中的检查时返回 None，否则返回说明。
+    pub fn problem(&self) -> Option<String> {
+        if !self.failing.is_empty() {
+            return Some(format!(
+                "CI checks are failing for HEAD: {}.",
+                self.failing.join(", ")
+            ));
+        }
+        if !self.pending.is_empty() {
+            return Some(format!(
+                "CI checks are still running for HEAD: {}.",
+                self.pending.join(", ")
+            ));
+        }
+        None
+    }
+}
+
+/// 查询 forge 上 sha 的 CI 状态。GitHub 使用 gh 调用 check-runs 和 status API，
+/// 其他 host 按 GitLab 处理，token 来自 GITLAB_TOKEN 或者 keychain。
+pub fn get_checks(
+    host: &str,
+    scope: &str,
+    repo: &str,
+    sha: &str,
+) -> Result<Checks, Box<dyn std::error::Error>> {
+    if forge::is_github(host) {
+        let check_runs = gh_api(
+            host,
+            format!("repos/{}/{}/commits/{}/check-runs", scope, repo, sha),
+        )?;
+        let status = gh_api(
+            host,
+            format!("repos/{}/{}/commits/{}/status", scope, repo, sha),
+        )?;
+        return Ok(github_checks(&check_runs, &status));
+    }
+    let project = format!("{}/{}", scope, repo).replace('/', "%2F");
+    let mut request = net::client()?.get(format!(
+        "{}/projects/{}/pipelines?sha={}",
+        forge::api_url(host),
+        project,
+        sha
+    ));
+    if let Some(token) = auth::token(host, &["GITLAB_TOKEN"]) {
+        request = request.header("PRIVATE-TOKEN", token);
+    }
+    let response = net::send(request)?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to get the pipelines from {}: HTTP {}",
+            host,
+            response.status().as_u16()
+        )
+        .into());
+    }
+    let pipelines: Value = serde_json::from_str(response.text()?.as_str())?;
+    Ok(gitlab_checks(&pipelines))
+}
+
+fn gh_api(host: &str, path: String) -> Result<Value, Box<dyn std::error::Error>> {
+    let output = net::gh_output(forge::gh(host).arg("api").arg(path))?
+        .ok_or("Network timeout exceeded while checking the CI status.")?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to check the CI status: {}",
+            net::gh_error(&output.stderr)
+        )
+        .into());
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+pub fn github_checks(check_runs: &Value, status: &Value) -> Checks {
+    let mut checks = Checks::default();
+    for run in check_runs["check_runs"].as_array().into_iter().flatten() {
+        let name = run["name"].as_str().unwrap_or("unknown").to_string();
+        if run["status"].as_str() != Some("completed") {
+            checks.pending.push(name);
+            continue;
+        }
+        if let Some("failure" | "cancelled" | "timed_out" | "action_required" | "startup_failure") =
+            run["conclusion"].as_str()
+        {
+            checks.failing.push(name);
+        }
+    }
+    // 没有任何 status 时 combined status 的 state 也是 pending，所以逐个判断
+    for status in status["statuses"].as_array().into_iter().flatten() {
+        let name = status["context"].as_str().unwrap_or("unknown").to_string();
+        match status["state"].as_str() {
+            Some("failure" | "error") => checks.failing.push(name),
+            Some("pending") => checks.pending.push(name),
+            _ => {}
+        }
+    }
+    checks
+}
+
+// 只看最新的 pipeline
+pub fn gitlab_checks(pipelines: &Value) -> Checks {
+    let mut checks = Checks::default();
+    let pipeline = match pipelines.as_array().and_then(|pipelines| pipelines.first()) {
+        Some(pipeline) => pipeline,
+        None => return checks,
+    };
+    let name = format!("pipeline #{}", pipeline["id"]);
+    match pipeline["status"].as_str() {
+        Some("failed" | "canceled") => checks.failing.push(name),
+        Some("success" | "skipped" | "manual") | None => {}
+        Some(_) => checks.pending.push(name),
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ci_checks() {
+        let check_runs = serde_json::json!({"check_runs": [
+            {"name": "test", "status": "completed", "conclusion": "success"},
+            {"name": "lint", "status": "completed", "conclusion": "failure"},
+            {"name": "build", "status": "in_progress", "conclusion": null},
+        ]});
+        let status = serde_json::json!({"state": "pending", "statuses": []});
+        let checks = github_checks(&check_runs, &status);
+        assert_eq!(checks.failing, vec!["lint"]);
+        assert_eq!(checks.pending, vec!["build"]);
+        assert!(checks.problem().unwrap().contains("failing"));
+        let check_runs = serde_json::json!({"check_runs": []});
+        assert_eq!(github_checks(&check_runs, &status).problem(), None);
+
+        let pipelines =
+            serde_json::json!([{"id": 2, "status": "running"}, {"id": 1, "status": "failed"}]);
+        assert_eq!(gitlab_checks(&pipelines).pending, vec!["pipeline #2"]);
+        assert_eq!(gitlab_checks(&serde_json::json!([])), Checks::default());
+    }
+}
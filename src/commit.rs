@@ -0,0 +1,281 @@
+use inquire::{Confirm, Select, Text};
+
+use crate::{config::Config, parse_author_from_line};
+
+pub fn commit(
+    path: &std::path::Path,
+    config: &Config,
+    author: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = git2::Repository::open(path)?;
+    let mut index = repo.index()?;
+    let tree_id = index.write_tree()?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    if let Some(parent) = &parent {
+        if parent.tree_id() == tree_id {
+            return Err("Nothing to commit, stage your changes first.".into());
+        }
+    }
+
+    let type_ = Select::new("Type:", config.types()).prompt()?;
+    // 配置了 allowed_scopes 时只能从中选择
+    let scope = match config
+        .allowed_scopes
+        .as_ref()
+        .filter(|scopes| !scopes.is_empty())
+    {
+        Some(scopes) => {
+            let mut options = vec!["(none)".to_string()];
+            options.extend(scopes.iter().cloned());
+            let scope = Select::new("Scope:", options).prompt()?;
+            if scope == "(none)" {
+                String::new()
+            } else {
+                scope
+            }
+        }
+        None => Text::new("Scope (optional):").prompt()?,
+    };
+    let description = Text::new("Description:")
+        .with_validator(inquire::required!("The description is required."))
+        .prompt()?;
+    // 和 git 一样，只有编辑 message 时才使用 commit.template
+    let template = read_commit_template(&repo).unwrap_or_default();
+    let body = Text::new("Body (optional):")
+        .with_initial_value(template.as_str())
+        .prompt()?;
+    let breaking = Confirm::new("Is this a breaking change?")
+        .with_default(false)
+        .prompt()?;
+    let mut footers = Vec::<String>::new();
+    if breaking {
+        let note = Text::new("Describe the breaking change:").prompt()?;
+        if !note.trim().is_empty() {
+            footers.push(format!("BREAKING CHANGE: {}", note.trim()));
+        }
+    }
+    loop {
+        let co_author = Text::new("Co-author (Name <mail>, empty to finish):").prompt()?;
+        if co_author.trim().is_empty() {
+            break;
+        }
+        let footer = format!("Co-authored-by: {}", co_author.trim());
+        if parse_author_from_line(footer.as_str()).is_none() {
+            println!("Co-authors must look like `Name <mail>`.");
+            continue;
+        }
+        footers.push(footer);
+    }
+
+    let message = format_commit_message(
+        type_.as_str(),
+        scope.trim(),
+        breaking,
+        description.trim(),
+        body.trim(),
+        &footers,
+    );
+    println!("\n{}\n", message);
+    if !Confirm::new("Create this commit?")
+        .with_default(true)
+        .prompt()?
+    {
+        return Ok(());
+    }
+
+    let id = create_commit(&repo, &mut index, message.as_str(), author)?;
+    log::info!("Created commit {}", id);
+    Ok(())
+}
+
+/// 使用 index 中的内容在 HEAD 上创建 commit。通过 `git commit` 创建，和 git 一样读取 `GIT_AUTHOR_*`、
+/// `GIT_COMMITTER_*` 和 commit.gpgsign，并运行 pre-commit 和 commit-msg hook。`--author` 只修改 author。
+pub fn create_commit(
+    repo: &git2::Repository,
+    index: &mut git2::Index,
+    message: &str,
+    author: Option<&str>,
+) -> Result<git2::Oid, Box<dyn std::error::Error>> {
+    index.write()?;
+    let workdir = repo.workdir().ok_or("The repository is bare.")?;
+    let mut command = std::process::Command::new("git");
+    command
+        .current_dir(workdir)
+        .arg("commit")
+        .arg("--quiet")
+        .arg("--allow-empty")
+        .arg("--cleanup=verbatim")
+        .arg("--file=-");
+    if let Some(author) = author {
+        let (name, mail) = parse_identity(author).ok_or(format!(
+            "`{}` is not a valid identity, use `Name <mail>`.",
+            author
+        ))?;
+        command.arg(format!("--author={} <{}>", name, mail));
+    }
+    let mut child = command
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        std::io::Write::write_all(&mut stdin, message.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to create the commit: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(repo.refname_to_id("HEAD")?)
+}
+
+/// `--author` 优先，否则和 git 一样从配置（包括 includeIf）中读取 user.name 和 user.email。
+pub fn get_signature<'a>(
+    repo: &git2::Repository,
+    author: Option<&str>,
+) -> Result<git2::Signature<'a>, Box<dyn std::error::Error>> {
+    if let Some(author) = author {
+        let (name, mail) = parse_identity(author).ok_or(format!(
+            "`{}` is not a valid identity, use `Name <mail>`.",
+            author
+        ))?;
+        return Ok(git2::Signature::now(name.as_str(), mail.as_str())?);
+    }
+    Ok(repo.signature()?)
+}
+
+pub fn parse_identity(identity: &str) -> Option<(String, String)> {
+    let re = regex!(r"^(?P<name>.+?) *<(?P<mail>[^<>]+)>$");
+    let captures = re.captures(identity.trim())?;
+    Some((
+        captures.name("name").unwrap().as_str().to_string(),
+        captures.name("mail").unwrap().as_str().to_string(),
+    ))
+}
+
+/// 读取 commit.template，去掉注释行。没有配置或内容为空时返回 None。
+pub fn read_commit_template(repo: &git2::Repository) -> Option<String> {
+    let template_path = repo.config().ok()?.get_path("commit.template").ok()?;
+    let template_path = match repo.workdir() {
+        Some(workdir) if template_path.is_relative() => workdir.join(template_path),
+        _ => template_path,
+    };
+    let content = std::fs::read_to_string(template_path).ok()?;
+    let content = content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let content = content.trim();
+    if content.is_empty() {
+        None
+    } else {
+        Some(content.to_string())
+    }
+}
+
+/// 生成 conventional commit 格式的 message，第一行可以被 `parse_first_line` 解析。
+pub fn format_commit_message(
+    type_: &str,
+    scope: &str,
+    breaking: bool,
+    description: &str,
+    body: &str,
+    footers: &[String],
+) -> String {
+    let mut message = type_.to_string();
+    if !scope.is_empty() {
+        message.push_str(format!("({})", scope).as_str());
+    }
+    if breaking {
+        message.push('!');
+    }
+    message.push_str(format!(": {}\n", description).as_str());
+    if !body.is_empty() {
+        message.push_str(format!("\n{}\n", body).as_str());
+    }
+    if !footers.is_empty() {
+        message.push('\n');
+        for footer in footers {
+            message.push_str(format!("{}\n", footer).as_str());
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gitt_tests::fixture, parse_author_from_body, parse_first_line};
+
+    #[test]
+    fn test_format_commit_message() {
+        let message = format_commit_message(
+            "feat",
+            "parser",
+            true,
+            "support scopes",
+            "",
+            &["Co-authored-by: Foo <foo@example.com>".to_string()],
+        );
+        assert_eq!(
+            message,
+            "feat(parser)!: support scopes\n\nCo-authored-by: Foo <foo@example.com>\n"
+        );
+        let (_, scope, description, type_, is_breaking) =
+            parse_first_line(message.lines().next().unwrap()).unwrap();
+        assert_eq!(
+            (scope.as_str(), description.as_str(), type_.as_str()),
+            ("parser", "support scopes", "feat")
+        );
+        assert!(is_breaking);
+        let mut authors = vec![];
+        parse_author_from_body(message.as_str(), &mut authors);
+        assert_eq!(authors[0].mail, "foo@example.com");
+    }
+
+    #[test]
+    fn test_parse_identity() {
+        assert_eq!(
+            parse_identity(
+                "github-actions[bot] <41898282+github-actions[bot]@users.noreply.github.com>"
+            ),
+            Some((
+                "github-actions[bot]".to_string(),
+                "41898282+github-actions[bot]@users.noreply.github.com".to_string()
+            ))
+        );
+        assert_eq!(parse_identity("nobody"), None);
+    }
+
+    #[test]
+    fn test_create_commit() {
+        let dir = fixture("no_tag");
+        let repo = git2::Repository::open(dir.path()).unwrap();
+        let mut index = repo.index().unwrap();
+        let id =
+            create_commit(&repo, &mut index, "chore: a", Some("Bot <bot@example.com>")).unwrap();
+        let commit = repo.find_commit(id).unwrap();
+        assert_eq!(commit.message(), Some("chore: a"));
+        // --author 不修改 committer
+        assert_eq!(commit.author().name(), Some("Bot"));
+        assert_eq!(commit.committer().name(), Some("tgit"));
+        assert!(create_commit(&repo, &mut index, "chore: b", Some("nobody")).is_err());
+
+        // 和 git 一样运行 hook
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let hook = dir.path().join(".git/hooks/pre-commit");
+            std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+            std::fs::write(&hook, "#!/bin/sh\necho rejected >&2\nexit 1\n").unwrap();
+            std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+            let err = create_commit(&repo, &mut index, "chore: c", None).unwrap_err();
+            assert!(err.to_string().contains("rejected"));
+            assert_eq!(repo.head().unwrap().target(), Some(id));
+        }
+    }
+}
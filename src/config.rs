@@ -0,0 +1,665 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+use crate::{
+    announce::Webhooks, email::Email, forge::Host, net::Network, policy::Policy, ready::Thresholds,
+    versions::Replacement, Options,
+};
+
+pub const CONFIG_FILE: &str = "tgit.toml";
+
+pub const DEFAULT_ALLOW_BRANCHES: [&str; 2] = ["main", "master"];
+
+pub const DEFAULT_TYPES: [&str; 11] = [
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+/// 仓库根目录下 tgit.toml 中的配置。所有字段都是可选的，未设置时使用命令行参数或默认值。
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// 版本 tag 的前缀，例如 `v`。
+    pub prefix: Option<String>,
+    /// 允许使用的 commit 类型。
+    pub types: Option<Vec<String>>,
+    /// 解析 commit 标题的正则，必须包含 `type` 和 `description` 分组。默认为 conventional commit。
+    pub commit_pattern: Option<String>,
+    /// 类型的别名，例如 `bugfix = "fix"`，解析时替换为对应的类型。
+    pub type_aliases: Option<HashMap<String, String>>,
+    /// 允许使用的 scope，`tgit lint` 和 `tgit commit` 会拒绝其他的 scope。未设置时不限制。
+    pub allowed_scopes: Option<Vec<String>>,
+    /// 发布 commit 是否使用 emoji。未设置时根据历史 commit 自动判断。
+    pub emoji: Option<bool>,
+    /// changelog 文件相对于仓库根目录的路径。
+    pub changelog: Option<String>,
+    /// 创建 changelog 文件时放在开头的内容，例如标题、badge 和介绍。默认 `# Changelog`，为空时不添加。
+    pub changelog_header: Option<String>,
+    /// changelog 文件末尾的内容，例如生成工具和链接，更新时保持在最后。默认没有。
+    pub changelog_footer: Option<String>,
+    /// 访问 forge API 时 token 的来源：`gh`、`env` 或 `none`。
+    pub token: Option<String>,
+    /// 自动发布时使用的 bot 身份，例如 `github-actions[bot] <mail>`。
+    pub bot: Option<String>,
+    /// 总是从 changelog 和版本推断中排除的 commit sha。
+    pub exclude_commits: Option<Vec<String>>,
+    /// 只在 changelog 中列出这些类型的 commit。
+    pub include_types: Option<Vec<String>>,
+    /// 不在 changelog 中列出的类型，例如 `chore`。
+    pub exclude_types: Option<Vec<String>>,
+    /// 不在 changelog 中列出的 scope。
+    pub exclude_scopes: Option<Vec<String>>,
+    /// 不在 changelog 中列出的作者名字或邮箱，支持 `*` 和 `?`。
+    pub exclude_authors: Option<Vec<String>>,
+    /// description 匹配这个正则的 commit 不在 changelog 中列出。
+    pub exclude_pattern: Option<String>,
+    /// 只沿着 merge commit 的第一个 parent 遍历，不列出合并进来的分支上的 commit。
+    pub first_parent: Option<bool>,
+    /// 不列出 merge commit。
+    pub no_merges: Option<bool>,
+    /// Contributors 中不列出 bot 账号，默认 true。
+    pub exclude_bots: Option<bool>,
+    /// 同一个版本中被 revert 的 commit 和 revert 一起移到 Reverts 中。
+    pub cancel_reverts: Option<bool>,
+    /// changelog 列出合并的 PR 而不是 commit，适合只用 squash merge 的仓库。
+    pub prs: Option<bool>,
+    /// 仓库的默认分支，未设置时从 remote 自动检测。
+    pub default_branch: Option<String>,
+    /// 允许发布的分支，支持 `*` 和 `?`，默认 `main` 和 `master`。
+    pub allow_branches: Option<Vec<String>>,
+    /// 无法解析的 commit 列在 Uncategorized 中，而不是只在最后提示。
+    pub include_unparsed: Option<bool>,
+    /// 正文中 conventional 的每一行作为单独的条目，适合 GitHub 默认的 squash message。
+    pub split_body: Option<bool>,
+    /// 在条目下显示 commit 的正文：`indent` 或 `details`，默认不显示。
+    pub include_body: Option<String>,
+    /// 在条目后面显示 breaking、security 之类的徽章。
+    pub badges: Option<bool>,
+    /// 打印到终端的 changelog 是否带颜色：`auto`、`always` 或 `never`，默认 `auto`。
+    pub color: Option<String>,
+    /// 打印 changelog 使用的 pager，为空时不使用，默认使用 TGIT_PAGER、PAGER 或 less。
+    pub pager: Option<String>,
+    /// `.changes/` 中的变更文件的用法：`append` 和 commit 一起列出，`only` 时只列出变更文件。默认 `append`。
+    pub changes: Option<String>,
+    /// 发布的 tag 已经存在时的处理方式：`error`、`overwrite` 或 `skip`，未设置时询问。
+    pub existing_tag: Option<String>,
+    /// release commit 推送到 remote 上的分支，未设置时使用当前分支的 upstream。
+    pub push_branch: Option<String>,
+    /// 不符合版本格式但仍作为版本使用的 tag，支持 `*` 和 `?`。
+    pub include_tags: Option<Vec<String>>,
+    /// 不作为版本使用的 tag，支持 `*` 和 `?`。
+    pub exclude_tags: Option<Vec<String>>,
+    /// 推断下一个版本的方式：`conventional`、`labels` 或 `paths`。
+    pub bump: Option<String>,
+    /// `paths` 中不会触发发布的文件，支持 `*` 和 `?`。
+    pub no_release_paths: Option<Vec<String>>,
+    /// changelog 中每个类型内的分组方式，目前只支持 `scope`。
+    pub group_by: Option<String>,
+    /// 缓存的 GitHub 用户名的有效天数，默认 30。
+    pub username_cache_ttl: Option<u64>,
+    /// 邮箱域名对应的组织，例如 `"example.com" = "Example Inc."`，子域名也属于同一个组织。
+    pub organizations: Option<HashMap<String, String>>,
+    /// Contributors 的分组方式，目前只支持 `organization`。
+    pub group_contributors_by: Option<String>,
+    /// changelog 每个条目最多显示的作者数量，其余的汇总为 "and N others"。
+    pub max_authors: Option<usize>,
+    /// 在 contributor 后面显示这个版本中的 commit 数量。
+    pub contributor_commits: Option<bool>,
+    /// 每个类型中条目的排序方式：`time` 按照提交的顺序，`subject` 按照标题。默认 `time`。
+    pub sort: Option<String>,
+    /// 首次发布没有可以比较的 tag，`tree` 链接到这个 tag 的文件，`none` 不显示链接。默认 `tree`。
+    pub first_release_link: Option<String>,
+    /// 版本号的格式：`semver` 或 `calver`。默认 `semver`。
+    pub scheme: Option<String>,
+    /// `calver` 时版本号的格式，例如 `YYYY.0M.MICRO`。默认 `YYYY.MM.MICRO`。
+    pub calver_format: Option<String>,
+    /// 0.x 版本中 breaking change 升级 minor、feat 升级 patch，而不是直接升级到 1.0.0。
+    /// 也可以写作 `semver_zero_policy`。
+    #[serde(alias = "semver_zero_policy")]
+    pub pre_major: Option<bool>,
+    /// 上一个版本是 pre-release 时，下一个版本默认递增 pre-release 的编号，例如 1.0.0-beta.1 -> 1.0.0-beta.2。
+    pub preserve_prerelease: Option<bool>,
+    /// 新版本的 build metadata，例如 `build.42` 或 `sha.{sha}`，`{sha}` 会被替换为 commit 的短 hash。
+    pub build_metadata: Option<String>,
+    /// 在每个版本后面加上记录 tgit 版本、commit 范围和配置哈希的 HTML 注释。
+    pub provenance: Option<bool>,
+    /// 记录版本的文件，`tgit check-versions` 和发布前检查它们的版本是否一致。
+    /// 默认是存在的 Cargo.toml、package.json 和 pyproject.toml。
+    pub version_files: Option<Vec<String>>,
+    /// bump 时在其他文件中替换版本，例如 README 的安装命令、Dockerfile 和 Helm chart。
+    pub replacements: Option<Vec<Replacement>>,
+    /// 自定义 host 的类型和 API 地址，例如 `[hosts."github.mycorp.com"]` 中 `forge = "github"`。
+    pub hosts: Option<HashMap<String, Host>>,
+    /// release commit 是否带上跳过 CI 的标记。
+    pub skip_ci: Option<bool>,
+    /// 发布前暂存未提交的修改和未跟踪的文件，发布后恢复。
+    pub autostash: Option<bool>,
+    /// 跳过 CI 的标记，例如 `[ci skip]` 或 `Skip-Checks: true`。默认 `[skip ci]`。
+    pub skip_ci_marker: Option<String>,
+    /// 发布前检查的处理方式。
+    pub policy: Policy,
+    /// `tgit ready` 判断需要发布的阈值。
+    pub ready: Thresholds,
+    /// `--announce` 时发布后通知的 webhook。
+    pub announce: Webhooks,
+    /// `--format email` 时邮件的发件人和收件人。
+    pub email: Email,
+    /// 代理和 TLS 证书。
+    pub network: Network,
+}
+
+impl Config {
+    pub fn load(path: &std::path::Path) -> Result<Config, Box<dyn std::error::Error>> {
+        let config_path = path.join(CONFIG_FILE);
+        if !config_path.exists() {
+            return Ok(Config::default());
+        }
+        let content = std::fs::read_to_string(config_path)?;
+        let config = toml::from_str(content.as_str())
+            .map_err(|err| format!("Failed to parse {}: {}", CONFIG_FILE, err))?;
+        Ok(config)
+    }
+
+    /// tgit.toml 内容的 FNV-1a 64 位哈希，没有配置文件时为空内容的哈希。
+    /// 不使用 DefaultHasher，它的结果在不同的 Rust 版本之间可能不同。
+    pub fn hash(path: &std::path::Path) -> String {
+        let content = std::fs::read(path.join(CONFIG_FILE)).unwrap_or_default();
+        let hash = content.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+        format!("fnv1a64:{:016x}", hash)
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        let content = toml::to_string(self)?;
+        crate::files::write_atomic(&path.join(CONFIG_FILE), content)?;
+        Ok(())
+    }
+
+    pub fn types(&self) -> Vec<String> {
+        match &self.types {
+            Some(types) => types.clone(),
+            None => DEFAULT_TYPES
+                .iter()
+                .map(|type_| type_.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn changelog_path(&self) -> &str {
+        self.changelog.as_deref().unwrap_or("CHANGELOG.md")
+    }
+
+    pub fn changelog_header(&self) -> Option<&str> {
+        let header = self.changelog_header.as_deref().unwrap_or("# Changelog");
+        (!header.trim().is_empty()).then_some(header)
+    }
+
+    pub fn changelog_footer(&self) -> Option<&str> {
+        let footer = self.changelog_footer.as_deref()?;
+        (!footer.trim().is_empty()).then_some(footer)
+    }
+}
+
+// `tgit config` 支持的 key，policy、ready、announce、email 和 network 中的字段使用对应的前缀，例如 `policy.dirty`
+pub const KEYS: [&str; 79] = [
+    "prefix",
+    "types",
+    "commit_pattern",
+    "type_aliases",
+    "allowed_scopes",
+    "emoji",
+    "changelog",
+    "changelog_header",
+    "changelog_footer",
+    "token",
+    "bot",
+    "exclude_commits",
+    "include_types",
+    "exclude_types",
+    "exclude_scopes",
+    "exclude_authors",
+    "exclude_pattern",
+    "prs",
+    "first_parent",
+    "no_merges",
+    "cancel_reverts",
+    "split_body",
+    "include_unparsed",
+    "exclude_bots",
+    "default_branch",
+    "allow_branches",
+    "push_branch",
+    "existing_tag",
+    "changes",
+    "include_tags",
+    "exclude_tags",
+    "bump",
+    "no_release_paths",
+    "group_by",
+    "max_authors",
+    "sort",
+    "contributor_commits",
+    "include_body",
+    "badges",
+    "color",
+    "pager",
+    "username_cache_ttl",
+    "organizations",
+    "hosts",
+    "group_contributors_by",
+    "first_release_link",
+    "scheme",
+    "calver_format",
+    "pre_major",
+    "preserve_prerelease",
+    "build_metadata",
+    "provenance",
+    "version_files",
+    "replacements",
+    "skip_ci",
+    "autostash",
+    "skip_ci_marker",
+    "policy.dirty",
+    "policy.untracked",
+    "policy.behind_remote",
+    "policy.unparsable_commits",
+    "policy.unparsable_ratio",
+    "policy.unsigned_commits",
+    "policy.ci_status",
+    "policy.versions",
+    "ready.breaking",
+    "ready.fix_age_days",
+    "ready.feat_age_days",
+    "ready.commits",
+    "ready.pending",
+    "announce.slack",
+    "announce.discord",
+    "announce.teams",
+    "email.from",
+    "email.to",
+    "network.proxy",
+    "network.no_proxy",
+    "network.ca_bundle",
+    "network.insecure",
+];
+
+/// 配置项的值以及它的来源。
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub value: Option<toml::Value>,
+    pub source: &'static str,
+}
+
+fn default_value(key: &str) -> Option<toml::Value> {
+    let value = match key {
+        "prefix" => "v".into(),
+        "types" => DEFAULT_TYPES.to_vec().into(),
+        "changelog" => "CHANGELOG.md".into(),
+        "changelog_header" => "# Changelog".into(),
+        "exclude_commits" | "include_tags" | "exclude_tags" | "include_types" | "exclude_types"
+        | "exclude_scopes" | "exclude_authors" | "allowed_scopes" => toml::Value::Array(vec![]),
+        "prs"
+        | "first_parent"
+        | "no_merges"
+        | "cancel_reverts"
+        | "contributor_commits"
+        | "split_body"
+        | "badges" => false.into(),
+        "exclude_bots" => true.into(),
+        "bump" => "conventional".into(),
+        "first_release_link" => "tree".into(),
+        "sort" => "time".into(),
+        "changes" => "append".into(),
+        "color" => "auto".into(),
+        "version_files" => crate::versions::DEFAULT_FILES.to_vec().into(),
+        "username_cache_ttl" => (crate::cache::DEFAULT_TTL_DAYS as i64).into(),
+        "skip_ci" | "autostash" | "pre_major" | "preserve_prerelease" | "provenance" => {
+            false.into()
+        }
+        "scheme" => "semver".into(),
+        "calver_format" => "YYYY.MM.MICRO".into(),
+        "skip_ci_marker" => "[skip ci]".into(),
+        "no_release_paths" => vec!["docs/*", "*.md"].into(),
+        "allow_branches" => DEFAULT_ALLOW_BRANCHES.to_vec().into(),
+        "policy.dirty" | "policy.versions" => "error".into(),
+        "policy.untracked" => true.into(),
+        "policy.behind_remote" => "warn".into(),
+        "policy.unparsable_commits" => "warn".into(),
+        "policy.unparsable_ratio" => 0.5.into(),
+        "policy.unsigned_commits" | "policy.ci_status" => "ignore".into(),
+        "ready.breaking" => true.into(),
+        "ready.fix_age_days" => 7.into(),
+        "ready.feat_age_days" => 14.into(),
+        "network.insecure" => false.into(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn flag_value(args: &Options, key: &str) -> Option<toml::Value> {
+    match key {
+        "prefix" => args.prefix.clone().map(toml::Value::from),
+        "bot" => args.as_bot.clone().map(toml::Value::from),
+        "exclude_commits" if !args.exclude_commit.is_empty() => {
+            Some(args.exclude_commit.clone().into())
+        }
+        "prs" if args.prs => Some(true.into()),
+        "first_parent" if args.first_parent => Some(true.into()),
+        "contributor_commits" if args.contributor_commits => Some(true.into()),
+        "no_merges" if args.no_merges => Some(true.into()),
+        "cancel_reverts" if args.cancel_reverts => Some(true.into()),
+        "split_body" if args.split_body => Some(true.into()),
+        "badges" if args.badges => Some(true.into()),
+        "include_unparsed" if args.include_unparsed => Some(true.into()),
+        "exclude_bots" if args.include_bots => Some(false.into()),
+        "include_types" if !args.include_types.is_empty() => {
+            Some(args.include_types.clone().into())
+        }
+        "exclude_types" if !args.exclude_types.is_empty() => {
+            Some(args.exclude_types.clone().into())
+        }
+        "exclude_scopes" if !args.exclude_scopes.is_empty() => {
+            Some(args.exclude_scopes.clone().into())
+        }
+        "exclude_authors" if !args.exclude_authors.is_empty() => {
+            Some(args.exclude_authors.clone().into())
+        }
+        "exclude_pattern" => args.exclude_pattern.clone().map(toml::Value::from),
+        "skip_ci" if args.skip_ci => Some(true.into()),
+        "autostash" if args.autostash => Some(true.into()),
+        "policy.dirty" if args.allow_dirty => Some("ignore".into()),
+        "pre_major" if args.pre_major => Some(true.into()),
+        "provenance" if args.provenance => Some(true.into()),
+        "preserve_prerelease" if args.preserve_prerelease => Some(true.into()),
+        "build_metadata" => args.build_metadata.clone().map(toml::Value::from),
+        "scheme" => args.scheme.clone().map(toml::Value::from),
+        "calver_format" => args.calver_format.clone().map(toml::Value::from),
+        "group_by" => args.group_by.clone().map(toml::Value::from),
+        "group_contributors_by" => args.group_contributors_by.clone().map(toml::Value::from),
+        "max_authors" => args.max_authors.map(|max| toml::Value::from(max as i64)),
+        "sort" => args.sort.clone().map(toml::Value::from),
+        "include_body" => args.include_body.clone().map(toml::Value::from),
+        "color" => args.color.clone().map(toml::Value::from),
+        "pager" => args.no_pager.then(|| "".into()),
+        "allow_branches" if !args.allow_branch.is_empty() => Some(args.allow_branch.clone().into()),
+        "existing_tag" => args.existing_tag.clone().map(toml::Value::from),
+        "push_branch" => args.push_branch.clone().map(toml::Value::from),
+        "network.insecure" if args.insecure => Some(true.into()),
+        _ => None,
+    }
+}
+
+fn file_value(table: &toml::Table, key: &str) -> Option<toml::Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.get(part)?;
+    }
+    Some(value.clone())
+}
+
+fn check_key(key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if KEYS.contains(&key) {
+        return Ok(());
+    }
+    Err(format!(
+        "Unknown config key `{}`, expected one of: {}.",
+        key,
+        KEYS.join(", ")
+    )
+    .into())
+}
+
+/// 环境变量中的值，只在 tgit.toml 没有设置时生效，例如 pager 和 proxy。
+fn env_value(key: &str, env: impl Fn(&str) -> Option<String>) -> Option<toml::Value> {
+    let vars: &[&str] = match key {
+        "pager" => &["TGIT_PAGER", "PAGER"],
+        "network.proxy" => &[
+            "HTTPS_PROXY",
+            "https_proxy",
+            "HTTP_PROXY",
+            "http_proxy",
+            "ALL_PROXY",
+            "all_proxy",
+        ],
+        "network.no_proxy" => &["NO_PROXY", "no_proxy"],
+        // NO_COLOR 只影响 auto
+        "color" if env("NO_COLOR").is_some_and(|value| !value.is_empty()) => {
+            return Some("never".into())
+        }
+        _ => return None,
+    };
+    vars.iter().find_map(|var| env(var)).map(toml::Value::from)
+}
+
+/// 按照 命令行参数 > tgit.toml > 环境变量 > 默认值 的顺序获取生效的值。
+pub fn get_entry(
+    args: &Options,
+    table: &toml::Table,
+    key: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Entry {
+    if let Some(value) = flag_value(args, key) {
+        return Entry {
+            value: Some(value),
+            source: "flag",
+        };
+    }
+    if let Some(value) = file_value(table, key) {
+        return Entry {
+            value: Some(value),
+            source: "file",
+        };
+    }
+    if let Some(value) = env_value(key, env) {
+        return Entry {
+            value: Some(value),
+            source: "env",
+        };
+    }
+    Entry {
+        value: default_value(key),
+        source: "default",
+    }
+}
+
+fn read_table(path: &std::path::Path) -> Result<toml::Table, Box<dyn std::error::Error>> {
+    let config_path = path.join(CONFIG_FILE);
+    if !config_path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let content = std::fs::read_to_string(config_path)?;
+    let table = content
+        .parse::<toml::Table>()
+        .map_err(|err| format!("Failed to parse {}: {}", CONFIG_FILE, err))?;
+    Ok(table)
+}
+
+fn format_entry(key: &str, entry: &Entry) -> String {
+    match &entry.value {
+        Some(value) => format!("{} = {} ({})", key, value, entry.source),
+        None => format!("{} is not set", key),
+    }
+}
+
+pub fn list(args: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let table = read_table(args.path.as_path())?;
+    for key in KEYS {
+        println!(
+            "{}",
+            format_entry(
+                key,
+                &get_entry(args, &table, key, |name| std::env::var(name).ok())
+            )
+        );
+    }
+    Ok(())
+}
+
+pub fn get(args: &Options, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    check_key(key)?;
+    let table = read_table(args.path.as_path())?;
+    println!(
+        "{}",
+        format_entry(
+            key,
+            &get_entry(args, &table, key, |name| std::env::var(name).ok())
+        )
+    );
+    Ok(())
+}
+
+/// 将值写回 tgit.toml，保留原来的格式和注释。
+pub fn set(args: &Options, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+    check_key(key)?;
+    let config_path = args.path.join(CONFIG_FILE);
+    let (content, ending) = if config_path.exists() {
+        crate::files::read(&config_path)?
+    } else {
+        (String::new(), crate::files::LineEnding::Lf)
+    };
+    let content = set_value(content.as_str(), key, value)?;
+    crate::files::write(&config_path, content.as_str(), ending)?;
+    Ok(())
+}
+
+/// 修改 tgit.toml 的内容中 key 的值。值按 TOML 解析，无法解析时作为字符串。
+pub fn set_value(
+    content: &str,
+    key: &str,
+    value: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut document = content
+        .parse::<DocumentMut>()
+        .map_err(|err| format!("Failed to parse {}: {}", CONFIG_FILE, err))?;
+    let value = value
+        .parse::<Value>()
+        .unwrap_or_else(|_| Value::from(value));
+    let (table, name) = match key.split_once('.') {
+        Some((section, name)) => (
+            document
+                .entry(section)
+                .or_insert(toml_edit::table())
+                .as_table_like_mut()
+                .ok_or(format!("`{}` in {} is not a table.", section, CONFIG_FILE))?,
+            name,
+        ),
+        None => (document.as_table_mut() as &mut dyn TableLike, key),
+    };
+    match table.get_mut(name).and_then(|item| item.as_value_mut()) {
+        // 保留值前后的空白和注释
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = value;
+            *old.decor_mut() = decor;
+        }
+        None => {
+            table.insert(name, Item::Value(value));
+        }
+    }
+    let content = document.to_string();
+    // 写入前检查类型是否正确
+    toml::from_str::<Config>(content.as_str())
+        .map_err(|err| format!("Invalid value for `{}`: {}", key, err))?;
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_get_entry() {
+        let args = Options::parse_from(vec!["tgit", "-p", "app-"]);
+        let table = "[policy]\ndirty = \"warn\"\npager = \"more\"\n"
+            .parse::<toml::Table>()
+            .unwrap();
+        let no_env = |_: &str| None;
+        let entry = get_entry(&args, &table, "prefix", no_env);
+        assert_eq!(entry.value, Some("app-".into()));
+        assert_eq!(entry.source, "flag");
+        let entry = get_entry(&args, &table, "policy.dirty", no_env);
+        assert_eq!(entry.value, Some("warn".into()));
+        assert_eq!(entry.source, "file");
+        let entry = get_entry(&args, &table, "policy.behind_remote", no_env);
+        assert_eq!(entry.value, Some("warn".into()));
+        assert_eq!(entry.source, "default");
+        assert_eq!(get_entry(&args, &table, "bot", no_env).value, None);
+    }
+
+    #[test]
+    fn test_env_entry() {
+        let args = Options::parse_from(vec!["tgit"]);
+        let env = |name: &str| match name {
+            "PAGER" => Some("most".to_string()),
+            "https_proxy" => Some("http://proxy:8080".to_string()),
+            "NO_COLOR" => Some("1".to_string()),
+            // tgit 不读取 TGIT_PREFIX
+            "TGIT_PREFIX" => Some("x".to_string()),
+            _ => None,
+        };
+        let empty = toml::Table::new();
+        let entry = get_entry(&args, &empty, "pager", env);
+        assert_eq!(entry.value, Some("most".into()));
+        assert_eq!(entry.source, "env");
+        let entry = get_entry(&args, &empty, "network.proxy", env);
+        assert_eq!(entry.value, Some("http://proxy:8080".into()));
+        assert_eq!(entry.source, "env");
+        assert_eq!(
+            get_entry(&args, &empty, "color", env).value,
+            Some("never".into())
+        );
+        assert_eq!(get_entry(&args, &empty, "prefix", env).source, "default");
+        // tgit.toml 优先于环境变量，命令行参数优先于两者
+        let table = "pager = \"less\"\ncolor = \"always\"\n"
+            .parse::<toml::Table>()
+            .unwrap();
+        assert_eq!(get_entry(&args, &table, "pager", env).source, "file");
+        assert_eq!(
+            get_entry(&args, &table, "color", env).value,
+            Some("always".into())
+        );
+        let args = Options::parse_from(vec!["tgit", "--no-pager"]);
+        assert_eq!(get_entry(&args, &table, "pager", env).source, "flag");
+    }
+
+    #[test]
+    fn test_set_value() {
+        let content =
+            "# tgit\nprefix = \"v\" # tags\n\n[policy]\n# before release\ndirty = \"warn\"\n";
+        assert_eq!(
+            set_value(content, "emoji", "true").unwrap(),
+            "# tgit\nprefix = \"v\" # tags\nemoji = true\n\n[policy]\n# before release\ndirty = \"warn\"\n"
+        );
+        assert_eq!(
+            set_value(content, "prefix", "app-").unwrap(),
+            "# tgit\nprefix = \"app-\" # tags\n\n[policy]\n# before release\ndirty = \"warn\"\n"
+        );
+        assert_eq!(
+            set_value(content, "policy.untracked", "false").unwrap(),
+            "# tgit\nprefix = \"v\" # tags\n\n[policy]\n# before release\ndirty = \"warn\"\nuntracked = false\n"
+        );
+        assert_eq!(
+            set_value("", "network.proxy", "http://proxy:8080").unwrap(),
+            "[network]\nproxy = \"http://proxy:8080\"\n"
+        );
+        assert!(set_value(content, "policy.dirty", "sometimes")
+            .unwrap_err()
+            .to_string()
+            .contains("policy.dirty"));
+        assert!(set_value("policy = 1\n", "policy.dirty", "warn").is_err());
+    }
+
+    #[test]
+    fn test_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        // 空内容的 FNV-1a 64 位哈希
+        assert_eq!(Config::hash(dir.path()), "fnv1a64:cbf29ce484222325");
+        std::fs::write(dir.path().join("tgit.toml"), "a").unwrap();
+        assert_eq!(Config::hash(dir.path()), "fnv1a64:af63dc4c8601ec8c");
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::render;
+
+/// tgit.toml 中的 `[email]`，`--format email` 时邮件的发件人和收件人。
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Email {
+    pub from: Option<String>,
+    pub to: Option<Vec<String>>,
+}
+
+/// RFC 5322 格式的邮件，正文是纯文本的 changelog，可以保存为 .eml 或交给 sendmail。
+pub fn message(email: &Email, subject: &str, changelog: &str) -> String {
+    let mut headers = Vec::<String>::new();
+    if let Some(from) = &email.from {
+        headers.push(format!("From: {}", from));
+    }
+    if let Some(to) = email.to.as_ref().filter(|to| !to.is_empty()) {
+        headers.push(format!("To: {}", to.join(", ")));
+    }
+    headers.push(format!("Subject: {}", encode_header(subject)));
+    headers.push(format!("Date: {}", chrono::Local::now().to_rfc2822()));
+    headers.push("MIME-Version: 1.0".to_string());
+    headers.push("Content-Type: text/plain; charset=utf-8".to_string());
+    headers.push("Content-Transfer-Encoding: 8bit".to_string());
+    let body = render::render(changelog.trim(), render::Format::Plain);
+    // 邮件的换行是 CRLF
+    format!("{}\n\n{}\n", headers.join("\n"), body).replace('\n', "\r\n")
+}
+
+/// 使用 `sendmail -t` 发送，收件人来自 To。
+pub fn sendmail(message: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = std::process::Command::new("sendmail")
+        .arg("-t")
+        .arg("-oi")
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Failed to run sendmail: {}", err))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(message.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("sendmail exited with {}.", status).into());
+    }
+    Ok(())
+}
+
+// 非 ASCII 的标题使用 RFC 2047 的 Q 编码
+fn encode_header(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    let encoded = text
+        .bytes()
+        .map(|byte| match byte {
+            b' ' => "_".to_string(),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' => (byte as char).to_string(),
+            byte => format!("={:02X}", byte),
+        })
+        .collect::<String>();
+    format!("=?UTF-8?Q?{}?=", encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_message() {
+        let email = Email {
+            from: Some("Release Bot <bot@example.com>".to_string()),
+            to: Some(vec!["dev@example.com".to_string()]),
+        };
+        let content = message(&email, "tgit v0.2.0 released", "## v0.2.0\n\n- **a**\n");
+        assert!(content.starts_with(
+            "From: Release Bot <bot@example.com>\r\nTo: dev@example.com\r\nSubject: tgit v0.2.0 released\r\n"
+        ));
+        assert!(content.contains("\r\nContent-Type: text/plain; charset=utf-8\r\n"));
+        assert!(content.ends_with("\r\n\r\nv0.2.0\r\n======\r\n\r\n- a\r\n"));
+        let content = message(&Email::default(), "发布 v1", "");
+        assert!(content.contains("Subject: =?UTF-8?Q?=E5=8F=91=E5=B8=83_v1?=\r\n"));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::push::PushError;
+
+// 脚本可以依赖的退出状态码，和 HELP 保持一致
+pub const ERROR: i32 = 1;
+pub const NOTHING_TO_RELEASE: i32 = 2;
+pub const DIRTY: i32 = 3;
+pub const LINT: i32 = 4;
+pub const POLICY: i32 = 5;
+pub const RELEASE_DUE: i32 = 6;
+pub const PUSH: i32 = 7;
+
+/// 显示在 `--help` 的最后。
+pub const HELP: &str = "Exit codes:
+  0  Success.
+  1  Error.
+  2  Nothing to release: no conventional commits left after the filters and no change
+     files since the latest tag.
+  3  The worktree is dirty (policy.dirty).
+  4  Commit messages do not follow the conventional commit format (tgit lint).
+  5  A release policy failed: the branch, the CI status, the upstream, the commits
+     or the version files (tgit check-versions).
+  6  A release is due (tgit ready).
+  7  The remote could not be verified before the release, or the push failed.";
+
+/// 上一个 tag 之后没有过滤后剩下的 conventional commit，也没有待发布的变更文件。
+#[derive(Debug)]
+pub struct NothingToRelease;
+
+impl std::fmt::Display for NothingToRelease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Nothing to release.")
+    }
+}
+
+impl std::error::Error for NothingToRelease {}
+
+/// 带有退出状态码的错误。
+#[derive(Debug)]
+pub struct Failure {
+    pub code: i32,
+    pub message: String,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+pub fn fail(code: i32, message: impl Into<String>) -> Box<dyn std::error::Error> {
+    Box::new(Failure {
+        code,
+        message: message.into(),
+    })
+}
+
+/// 错误对应的退出状态码，没有指定时是 ERROR。
+pub fn code(err: &(dyn std::error::Error + 'static)) -> i32 {
+    if err.is::<NothingToRelease>() {
+        return NOTHING_TO_RELEASE;
+    }
+    if err.is::<PushError>() {
+        return PUSH;
+    }
+    match err.downcast_ref::<Failure>() {
+        Some(failure) => failure.code,
+        None => ERROR,
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// 文件使用的换行符。读取时统一为 `\n`，写回时恢复，避免 Windows 上 CRLF 的文件被改成混合的换行。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn detect(text: &str) -> Self {
+        if text.contains("\r\n") {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// text 中的换行是 `\n`。
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            LineEnding::Lf => text.to_string(),
+            LineEnding::Crlf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// 读取文本文件，换行统一为 `\n`，同时返回文件原来的换行符。
+pub fn read(path: &Path) -> std::io::Result<(String, LineEnding)> {
+    let content = std::fs::read_to_string(path)?;
+    let ending = LineEnding::detect(content.as_str());
+    Ok((content.replace("\r\n", "\n"), ending))
+}
+
+/// 使用 ending 写入文本文件，content 中的换行是 `\n`。
+pub fn write(path: &Path, content: &str, ending: LineEnding) -> std::io::Result<()> {
+    write_atomic(path, ending.apply(content))
+}
+
+/// 先写入同一目录下的临时文件再重命名，失败时原文件保持不变，也不会留下旧内容的残余。
+/// 已存在的文件保留原来的权限。
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(contents.as_ref())?;
+    file.as_file().sync_all()?;
+    match std::fs::metadata(path) {
+        Ok(metadata) => std::fs::set_permissions(file.path(), metadata.permissions())?,
+        // 临时文件只有所有者可以读写，新文件使用和 fs::write 一样的常见权限
+        #[cfg(unix)]
+        Err(_) => {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(file.path(), std::fs::Permissions::from_mode(0o644))?;
+        }
+        #[cfg(not(unix))]
+        Err(_) => {}
+    }
+    file.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+/// 已存在的文件使用的换行符，不存在时是 `\n`。
+pub fn line_ending(path: &Path) -> LineEnding {
+    std::fs::read_to_string(path)
+        .map(|content| LineEnding::detect(content.as_str()))
+        .unwrap_or(LineEnding::Lf)
+}
+
+/// 用户目录，Windows 上没有 HOME 时使用 USERPROFILE。
+pub fn home_dir() -> Option<PathBuf> {
+    ["HOME", "USERPROFILE"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .find(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
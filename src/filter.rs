@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::{compile_globs, config::Config, Author, Commit, Glob, Options};
+
+/// 决定哪些 commit 出现在 changelog 中。命令行参数和 tgit.toml 中的配置会合并。
+pub struct CommitFilter {
+    include_types: Vec<String>,
+    exclude_types: Vec<String>,
+    exclude_scopes: Vec<String>,
+    /// 作者的名字或邮箱，支持 `*` 和 `?`。
+    exclude_authors: Vec<Glob>,
+    /// 匹配 description 的正则。
+    exclude_pattern: Option<Regex>,
+    /// 同一个版本中被 revert 的 commit 和 revert 一起移到 revert 中。
+    cancel_reverts: bool,
+}
+
+impl CommitFilter {
+    pub fn new(args: &Options, config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let merge = |args: &Vec<String>, config: &Option<Vec<String>>| {
+            let mut values = args.clone();
+            values.extend(config.clone().unwrap_or_default());
+            values
+        };
+        let exclude_pattern = match args
+            .exclude_pattern
+            .as_ref()
+            .or(config.exclude_pattern.as_ref())
+        {
+            Some(pattern) => Some(
+                Regex::new(pattern)
+                    .map_err(|err| format!("Invalid exclude pattern `{}`: {}", pattern, err))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            include_types: merge(&args.include_types, &config.include_types),
+            exclude_types: merge(&args.exclude_types, &config.exclude_types),
+            exclude_scopes: merge(&args.exclude_scopes, &config.exclude_scopes),
+            exclude_authors: compile_globs(&merge(&args.exclude_authors, &config.exclude_authors))?,
+            exclude_pattern,
+            cancel_reverts: args.cancel_reverts || config.cancel_reverts.unwrap_or(false),
+        })
+    }
+
+    pub fn keep(&self, commit: &Commit) -> bool {
+        if !self.include_types.is_empty() && !self.include_types.contains(&commit.type_) {
+            return false;
+        }
+        if self.exclude_types.contains(&commit.type_) {
+            return false;
+        }
+        if !commit.scope.is_empty() && self.exclude_scopes.contains(&commit.scope) {
+            return false;
+        }
+        // 只看主要作者，co-author 是 bot 时仍然保留
+        if let Some(author) = commit.authors.first() {
+            if self.exclude_authors.iter().any(|glob| {
+                glob.is_match(author.name.as_str()) || glob.is_match(author.mail.as_str())
+            }) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.exclude_pattern {
+            if pattern.is_match(commit.description.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 去掉被过滤的 commit 和只出现在这些 commit 中的 contributor，同时统计 contributor 剩下的 commit 数量，
+    /// 返回剩下的 commit 中是否有 breaking change。
+    pub fn apply(
+        &self,
+        commit_map: &mut HashMap<String, Vec<Commit>>,
+        contributors: &mut HashMap<String, Author>,
+    ) -> bool {
+        for commits in commit_map.values_mut() {
+            commits.retain(|commit| self.keep(commit));
+        }
+        dedup_commits(commit_map);
+        if self.cancel_reverts {
+            cancel_reverts(commit_map);
+        }
+        commit_map.retain(|_, commits| !commits.is_empty());
+        contributors.retain(|mail, contributor| {
+            contributor.commits = commit_map
+                .values()
+                .flatten()
+                .filter(|commit| commit.authors.iter().any(|author| &author.mail == mail))
+                .count();
+            contributor.commits > 0
+        });
+        commit_map
+            .values()
+            .flatten()
+            .any(|commit| commit.is_breaking)
+    }
+}
+
+/// 合并类型、scope 和 description 都相同的 commit（cherry-pick 或重新提交），其余的 hash 记录在第一个条目中。
+fn dedup_commits(commit_map: &mut HashMap<String, Vec<Commit>>) {
+    for commits in commit_map.values_mut() {
+        let mut deduped = Vec::<Commit>::with_capacity(commits.len());
+        for commit in commits.drain(..) {
+            let same = deduped
+                .iter_mut()
+                .find(|kept| kept.scope == commit.scope && kept.description == commit.description);
+            let kept = match same {
+                Some(kept) => kept,
+                None => {
+                    deduped.push(commit);
+                    continue;
+                }
+            };
+            kept.duplicates.push(commit.hash);
+            kept.duplicates.extend(commit.duplicates);
+            kept.is_breaking |= commit.is_breaking;
+            kept.pr = kept.pr.or(commit.pr);
+            for author in commit.authors {
+                if !kept.authors.iter().any(|kept| kept.mail == author.mail) {
+                    kept.authors.push(author);
+                }
+            }
+            for badge in commit.badges {
+                if !kept.badges.contains(&badge) {
+                    kept.badges.push(badge);
+                }
+            }
+        }
+        *commits = deduped;
+    }
+}
+
+/// 把被 revert 的 commit 从原来的类型中移到对应的 revert 之前，并且不再视为 breaking change。
+/// 被 revert 的 commit 不在这个版本中时，revert 照常列出。
+fn cancel_reverts(commit_map: &mut HashMap<String, Vec<Commit>>) {
+    let reverts = match commit_map.remove("revert") {
+        Some(reverts) => reverts,
+        None => return,
+    };
+    let mut commits = Vec::<Commit>::new();
+    for revert in reverts {
+        let reverted = revert.reverts.as_ref().and_then(|sha| {
+            commit_map.values_mut().find_map(|commits| {
+                let index = commits
+                    .iter()
+                    .position(|commit| commit.hash.starts_with(sha.as_str()))?;
+                Some(commits.remove(index))
+            })
+        });
+        if let Some(mut reverted) = reverted {
+            reverted.description = format!("~~{}~~", reverted.description);
+            reverted.is_breaking = false;
+            commits.push(reverted);
+        }
+        commits.push(revert);
+    }
+    commit_map.insert("revert".to_string(), commits);
+}
+
+// 常见的没有 [bot] 后缀的 bot 账号
+const BOTS: [&str; 5] = [
+    "dependabot",
+    "renovate",
+    "github-actions",
+    "greenkeeper",
+    "snyk-bot",
+];
+
+/// 是否是 bot 账号：`[bot]` 结尾的名字或用户名、常见的 bot 以及 noreply 邮箱。
+/// `<id>+<login>@users.noreply.github.com` 是用户隐藏的邮箱，不视为 bot。
+pub fn is_bot(author: &Author) -> bool {
+    let name = author.name.to_lowercase();
+    let username = author.username.to_lowercase();
+    if name.ends_with("[bot]") || username.ends_with("[bot]") {
+        return true;
+    }
+    if BOTS.contains(&name.as_str()) || BOTS.contains(&username.as_str()) {
+        return true;
+    }
+    let mail = author.mail.to_lowercase();
+    let local = mail.split('@').next().unwrap_or("");
+    local.contains("[bot]") || local == "noreply" || local == "no-reply"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_reverted, parse_subject};
+    use clap::Parser;
+
+    #[test]
+    fn test_commit_filter() {
+        let commit = |type_: &str, scope: &str, description: &str, mail: &str| {
+            Commit::new(
+                "0".repeat(40),
+                type_.to_string(),
+                scope.to_string(),
+                description.to_string(),
+                type_ == "feat",
+                vec![Author {
+                    name: mail.to_string(),
+                    mail: mail.to_string(),
+                    username: "".to_string(),
+                    commits: 0,
+                }],
+                vec![],
+            )
+        };
+        let args = Options::parse_from(vec![
+            "tgit",
+            "--exclude-types",
+            "chore,ci",
+            "--exclude-scopes",
+            "deps",
+            "--exclude-authors",
+            "*[bot]*",
+            "--exclude-pattern",
+            "^wip",
+        ]);
+        let filter = CommitFilter::new(&args, &Config::default()).unwrap();
+        let mut commit_map = HashMap::from([
+            (
+                "feat".to_string(),
+                vec![commit("feat", "", "wip parser", "a@example.com")],
+            ),
+            (
+                "fix".to_string(),
+                vec![
+                    commit("fix", "deps", "bump regex", "a@example.com"),
+                    commit("fix", "", "bump regex", "dependabot[bot]@example.com"),
+                    commit("fix", "cli", "handle empty input", "b@example.com"),
+                ],
+            ),
+            (
+                "chore".to_string(),
+                vec![commit("chore", "", "release", "c@example.com")],
+            ),
+        ]);
+        let mut contributors = HashMap::new();
+        for commit in commit_map.values().flatten() {
+            let author = commit.authors[0].clone();
+            contributors.insert(author.mail.clone(), author);
+        }
+        assert!(!filter.apply(&mut commit_map, &mut contributors));
+        assert_eq!(commit_map.keys().collect::<Vec<_>>(), vec!["fix"]);
+        assert_eq!(commit_map["fix"][0].description, "handle empty input");
+        assert_eq!(
+            contributors.keys().collect::<Vec<_>>(),
+            vec!["b@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_cancel_reverts() {
+        let commit = |hash: &str, subject: &str, body: &str| {
+            let (_, scope, description, type_, is_breaking) = parse_subject(subject).unwrap();
+            let mut commit = Commit::new(
+                hash.repeat(40),
+                type_,
+                scope,
+                description,
+                is_breaking,
+                vec![],
+                vec![],
+            );
+            commit.reverts = parse_reverted(body);
+            commit
+        };
+        let mut commit_map = HashMap::<String, Vec<Commit>>::new();
+        for commit in [
+            commit("a", "feat!: add thing", ""),
+            commit("b", "fix: keep this", ""),
+            commit(
+                "c",
+                "Revert \"feat!: add thing\"",
+                &format!("This reverts commit {}.", "a".repeat(40)),
+            ),
+        ] {
+            commit_map
+                .entry(commit.type_.clone())
+                .or_default()
+                .push(commit);
+        }
+        let args = Options::parse_from(vec!["tgit", "--cancel-reverts"]);
+        let filter = CommitFilter::new(&args, &Config::default()).unwrap();
+        assert!(!filter.apply(&mut commit_map, &mut HashMap::new()));
+        assert!(!commit_map.contains_key("feat"));
+        let reverts = commit_map["revert"]
+            .iter()
+            .map(|commit| commit.description.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(reverts, vec!["~~add thing~~", "feat!: add thing"]);
+    }
+
+    #[test]
+    fn test_is_bot() {
+        let author = |name: &str, mail: &str, username: &str| Author {
+            name: name.to_string(),
+            mail: mail.to_string(),
+            username: username.to_string(),
+            commits: 0,
+        };
+        assert!(is_bot(&author(
+            "dependabot[bot]",
+            "49699333+dependabot[bot]@users.noreply.github.com",
+            ""
+        )));
+        assert!(is_bot(&author(
+            "Renovate Bot",
+            "bot@renovateapp.com",
+            "renovate"
+        )));
+        assert!(is_bot(&author("GitHub", "noreply@github.com", "")));
+        assert!(!is_bot(&author(
+            "Jannchie",
+            "29743310+Jannchie@users.noreply.github.com",
+            "Jannchie"
+        )));
+    }
+}
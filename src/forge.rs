@@ -0,0 +1,979 @@
+use std::{collections::HashMap, process::Command, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    auth, fetch_github_username, get_pull_requests, net, parse_github_commit, progress,
+    uncategorized_github_commit, Commit, RemoteInfo,
+};
+
+// 启动时由 tgit.toml 的 `[hosts]` 配置
+static HOSTS: OnceLock<Hosts> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Github,
+    Gitlab,
+    Gitea,
+}
+
+/// tgit.toml 中的 `[hosts."github.mycorp.com"]`，用于 GitHub Enterprise 之类名字中看不出类型的 host。
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Host {
+    /// `github`、`gitlab` 或 `gitea`。
+    pub forge: Option<Kind>,
+    /// API 的地址，例如 `https://github.mycorp.com/api/v3`。GitHub 的请求通过 gh 发送，gh 根据 hostname 决定地址。
+    pub api: Option<String>,
+    /// 网页的地址，用于 commit 和 compare 链接，例如 remote 使用 SSH 别名时。默认 `https://<host>`。
+    pub web: Option<String>,
+}
+
+/// 配置的 host，key 是 remote URL 中的 host。
+#[derive(Debug, Default, Clone)]
+pub struct Hosts(pub HashMap<String, Host>);
+
+impl Hosts {
+    fn get(&self, host: &str) -> Option<&Host> {
+        self.0.get(host)
+    }
+
+    /// host 的类型，没有配置时根据名字判断。
+    pub fn kind(&self, host: &str) -> Option<Kind> {
+        if let Some(kind) = self.get(host).and_then(|configured| configured.forge) {
+            return Some(kind);
+        }
+        if host.contains("github") {
+            Some(Kind::Github)
+        } else if host.contains("gitlab") {
+            Some(Kind::Gitlab)
+        } else if host.contains("gitea") || host == "codeberg.org" {
+            Some(Kind::Gitea)
+        } else {
+            None
+        }
+    }
+
+    /// 仓库网页的地址前缀，不带结尾的 `/`。
+    pub fn web_url(&self, host: &str) -> String {
+        match self
+            .get(host)
+            .and_then(|configured| configured.web.as_deref())
+        {
+            Some(web) => web.trim_end_matches('/').to_string(),
+            None => format!("https://{}", host),
+        }
+    }
+
+    /// API 的地址，不带结尾的 `/`。GitHub Enterprise 默认是 `/api/v3`，未知的 host 按 GitLab 处理。
+    pub fn api_url(&self, host: &str) -> String {
+        if let Some(api) = self
+            .get(host)
+            .and_then(|configured| configured.api.as_deref())
+        {
+            return api.trim_end_matches('/').to_string();
+        }
+        match self.kind(host) {
+            Some(Kind::Github) if host == "github.com" => "https://api.github.com".to_string(),
+            Some(Kind::Github) => format!("https://{}/api/v3", host),
+            Some(Kind::Gitea) => format!("https://{}/api/v1", host),
+            _ => format!("https://{}/api/v4", host),
+        }
+    }
+
+    /// gh 使用的 hostname，配置了 web 时取它的 host。
+    pub fn gh_host(&self, host: &str) -> String {
+        let web = self.web_url(host);
+        web.split("://")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or(host)
+            .to_string()
+    }
+}
+
+pub fn configure(hosts: HashMap<String, Host>) {
+    let _ = HOSTS.set(Hosts(hosts));
+}
+
+fn hosts() -> &'static Hosts {
+    HOSTS.get_or_init(Hosts::default)
+}
+
+pub fn is_github(host: &str) -> bool {
+    hosts().kind(host) == Some(Kind::Github)
+}
+
+pub fn web_url(host: &str) -> String {
+    hosts().web_url(host)
+}
+
+pub fn api_url(host: &str) -> String {
+    hosts().api_url(host)
+}
+
+pub fn gh_host(host: &str) -> String {
+    hosts().gh_host(host)
+}
+
+/// 访问 host 的 gh 命令，GitHub Enterprise 通过 GH_HOST 指定实例。
+/// 没有设置 token 的环境变量时使用 `tgit auth login` 保存的 token。
+pub fn gh(host: &str) -> Command {
+    let mut command = net::gh();
+    let gh_host = gh_host(host);
+    let token_var = if gh_host == "github.com" {
+        "GH_TOKEN"
+    } else {
+        command.env("GH_HOST", gh_host.as_str());
+        "GH_ENTERPRISE_TOKEN"
+    };
+    if std::env::var_os(token_var).is_none() && std::env::var_os("GITHUB_TOKEN").is_none() {
+        if let Some(token) = auth::stored_token(host) {
+            command.env(token_var, token);
+        }
+    }
+    command
+}
+
+/// forge 或本地的 commit 解析后的结果。
+pub enum Parsed {
+    /// 解析后的 commit 和标题中的 emoji
+    Conventional(Commit, String),
+    /// 无法解析的 commit，使用完整的标题作为 description
+    Uncategorized(Commit),
+}
+
+pub struct FetchedCommit {
+    pub sha: String,
+    pub is_merge: bool,
+    pub parsed: Parsed,
+}
+
+type Error = Box<dyn std::error::Error>;
+
+/// 托管仓库的服务。changelog 的链接、commit 的作者信息和 release 都通过它获取，
+/// 不支持的功能使用本地的 git 历史代替。
+pub trait Forge: Sync {
+    fn kind(&self) -> Option<Kind>;
+
+    /// 仓库的网页地址，为 None 时 changelog 中没有链接。
+    fn web_url(&self) -> Option<String>;
+
+    fn commit_url(&self, sha: &str) -> Option<String> {
+        Some(format!("{}/commit/{}", self.web_url()?, sha))
+    }
+
+    fn compare_url(&self, from: &str, to: &str) -> Option<String> {
+        Some(format!("{}/compare/{}...{}", self.web_url()?, from, to))
+    }
+
+    fn tree_url(&self, rev: &str) -> Option<String> {
+        Some(format!("{}/tree/{}", self.web_url()?, rev))
+    }
+
+    fn pull_request_url(&self, number: u64) -> Option<String> {
+        Some(format!("{}/pull/{}", self.web_url()?, number))
+    }
+
+    /// from（不包含）到 to 的 commit，新的在前，from 为 None 时直到第一个 commit。
+    /// 作者的用户名记录在 mail_to_login 中。不支持或者失败时返回 None，使用本地的 git 历史。
+    fn fetch_commits(
+        &self,
+        _from: Option<&str>,
+        _to: &str,
+        _mail_to_login: &mut HashMap<String, String>,
+    ) -> Result<Option<Vec<FetchedCommit>>, Error> {
+        Ok(None)
+    }
+
+    /// since（unix 时间戳）之后合并的 PR，key 是 merge commit 的 sha。不支持或者超时时返回 None。
+    fn fetch_pull_requests(&self, _since: i64) -> Result<Option<HashMap<String, Value>>, Error> {
+        Ok(None)
+    }
+
+    /// 邮箱对应的用户名。
+    fn resolve_username(&self, _email: &str) -> Option<String> {
+        None
+    }
+
+    /// 用户名缓存中邮箱的 key。GitHub 的用户名直接使用邮箱，其他 forge 加上 host。
+    fn cache_key(&self, email: &str) -> String {
+        email.to_string()
+    }
+
+    /// 为已经推送的 tag 创建 release，返回 release 的 URL。
+    fn create_release(&self, tag: &str, title: &str, notes: &str) -> Result<String, Error>;
+}
+
+/// remote 对应的 forge。
+pub fn detect(info: Option<RemoteInfo>) -> Box<dyn Forge> {
+    match info {
+        Some(info) => match hosts().kind(&info.host) {
+            Some(Kind::Github) => Box::new(GitHub { info }),
+            Some(Kind::Gitlab) => Box::new(GitLab {
+                api: api_url(&info.host),
+                info,
+            }),
+            Some(Kind::Gitea) => Box::new(Gitea {
+                api: api_url(&info.host),
+                info,
+            }),
+            None => Box::new(NoForge { info: Some(info) }),
+        },
+        None => Box::new(NoForge { info: None }),
+    }
+}
+
+/// GitHub API 格式的 commit，Gitea 的 commit 也是这个格式。
+fn fetched_commit(
+    raw_commit: &Value,
+    mail_to_login: &mut HashMap<String, String>,
+) -> Result<FetchedCommit, Error> {
+    let raw_commit = raw_commit
+        .as_object()
+        .ok_or("Unexpected commit in the response.")?;
+    let is_merge = raw_commit
+        .get("parents")
+        .and_then(|parents| parents.as_array())
+        .is_some_and(|parents| parents.len() > 1);
+    let parsed = match parse_github_commit(raw_commit, mail_to_login) {
+        Some((commit, emoji)) => Parsed::Conventional(commit, emoji),
+        None => Parsed::Uncategorized(uncategorized_github_commit(raw_commit)),
+    };
+    Ok(FetchedCommit {
+        sha: raw_commit["sha"].as_str().unwrap_or("").to_string(),
+        is_merge,
+        parsed,
+    })
+}
+
+/// 发送 GET 请求并解析 JSON，超时时返回 None。
+fn get_json(
+    request: reqwest::blocking::RequestBuilder,
+    host: &str,
+) -> Result<Option<Value>, Error> {
+    if net::expired() {
+        return Ok(None);
+    }
+    let response = net::send(request)?;
+    if net::rate_limited(&response) {
+        return Err(net::rate_limit_error(host).into());
+    }
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status().as_u16()).into());
+    }
+    let body = response.text()?;
+    progress::emit(progress::Event::BytesFetched { bytes: body.len() });
+    Ok(Some(serde_json::from_str(body.as_str())?))
+}
+
+/// 依次获取每一页的 commit，直到不满 per_page 个。失败或者超时时返回 None，使用本地的 git 历史。
+fn fetch_commit_pages(
+    request: impl Fn(usize) -> Result<reqwest::blocking::RequestBuilder, Error>,
+    per_page: usize,
+    host: &str,
+) -> Option<Vec<Value>> {
+    let mut raw_commits = Vec::new();
+    for page in 1.. {
+        let commits = match request(page).and_then(|request| get_json(request, host)) {
+            Ok(Some(data)) => match data {
+                Value::Array(commits) => commits,
+                _ => {
+                    log::warn!(
+                        "unexpected response from {}, using the local history.",
+                        host
+                    );
+                    return None;
+                }
+            },
+            Ok(None) => {
+                net::warn_offline(format!("fetching commits from {}", host).as_str());
+                return None;
+            }
+            Err(err) => {
+                log::warn!(
+                    "failed to fetch commits from {}, using the local history: {}",
+                    host,
+                    err
+                );
+                return None;
+            }
+        };
+        let count = commits.len();
+        raw_commits.extend(commits);
+        if count < per_page {
+            break;
+        }
+    }
+    Some(raw_commits)
+}
+
+pub struct GitHub {
+    pub info: RemoteInfo,
+}
+
+impl GitHub {
+    fn repo_path(&self) -> String {
+        format!("repos/{}/{}", self.info.scope, self.info.repo)
+    }
+}
+
+impl Forge for GitHub {
+    fn kind(&self) -> Option<Kind> {
+        Some(Kind::Github)
+    }
+
+    fn web_url(&self) -> Option<String> {
+        Some(self.info.web_url())
+    }
+
+    fn fetch_commits(
+        &self,
+        from: Option<&str>,
+        to: &str,
+        mail_to_login: &mut HashMap<String, String>,
+    ) -> Result<Option<Vec<FetchedCommit>>, Error> {
+        // 有 from 时使用 compare，按照从旧到新的顺序返回；否则从 to 开始列出全部的历史
+        let path = |page: usize| match from {
+            Some(from) => format!(
+                "{}/compare/{}...{}?per_page=100&page={}",
+                self.repo_path(),
+                from,
+                to,
+                page
+            ),
+            None => format!(
+                "{}/commits?per_page=100&page={}&sha={}",
+                self.repo_path(),
+                page,
+                to
+            ),
+        };
+        let mut raw_commits = Vec::<Value>::new();
+        // 每一批并行获取多个分页，批次的大小逐渐增加，范围较小时不会多请求太多分页
+        let mut page = 1;
+        let mut batch = 1;
+        'pages: loop {
+            let pages = net::parallel((page..page + batch).collect(), |page| {
+                net::gh_output(gh(&self.info.host).arg("api").arg(path(page)))
+            });
+            page += batch;
+            batch = (batch * 2).min(net::CONCURRENCY);
+            for output in pages {
+                let output = match output {
+                    Ok(Some(output)) => output,
+                    Ok(None) => {
+                        net::warn_offline("fetching commits from GitHub");
+                        return Ok(None);
+                    }
+                    Err(err) => {
+                        log::warn!("failed to run gh, using the local history: {}", err);
+                        return Ok(None);
+                    }
+                };
+                if !output.status.success() {
+                    log::warn!(
+                        "failed to fetch commits from GitHub, using the local history: {}",
+                        net::gh_error(&output.stderr)
+                    );
+                    return Ok(None);
+                }
+                progress::emit(progress::Event::BytesFetched {
+                    bytes: output.stdout.len(),
+                });
+                let data: Value = serde_json::from_slice(&output.stdout)?;
+                let commits = match from {
+                    Some(_) => data["commits"].as_array(),
+                    None => data.as_array(),
+                }
+                .ok_or("Unexpected response from GitHub.")?;
+                raw_commits.extend(commits.iter().cloned());
+                if commits.len() < 100 {
+                    break 'pages;
+                }
+            }
+        }
+        if from.is_some() {
+            raw_commits.reverse();
+        }
+        let fetched = raw_commits
+            .iter()
+            .map(|raw_commit| fetched_commit(raw_commit, mail_to_login))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(fetched))
+    }
+
+    fn fetch_pull_requests(&self, since: i64) -> Result<Option<HashMap<String, Value>>, Error> {
+        get_pull_requests(&self.info.host, &self.info.scope, &self.info.repo, since)
+    }
+
+    fn resolve_username(&self, email: &str) -> Option<String> {
+        fetch_github_username(email).ok()
+    }
+
+    fn create_release(&self, tag: &str, title: &str, notes: &str) -> Result<String, Error> {
+        let output = net::output(
+            gh(&self.info.host)
+                .arg("release")
+                .arg("create")
+                .arg(tag)
+                .arg("--repo")
+                .arg(format!(
+                    "{}/{}/{}",
+                    gh_host(&self.info.host),
+                    self.info.scope,
+                    self.info.repo
+                ))
+                .arg("--title")
+                .arg(title)
+                .arg("--notes")
+                .arg(notes),
+        )?
+        .ok_or("Network timeout exceeded while creating the release.")?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to create the release: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+pub struct GitLab {
+    pub info: RemoteInfo,
+    /// API 的地址，例如 `https://gitlab.com/api/v4`
+    pub api: String,
+}
+
+impl GitLab {
+    fn project_url(&self) -> String {
+        let project = format!("{}/{}", self.info.scope, self.info.repo).replace('/', "%2F");
+        format!("{}/projects/{}", self.api, project)
+    }
+
+    /// token 来自 GITLAB_TOKEN 或者 keychain。
+    fn get(&self, url: String) -> Result<reqwest::blocking::RequestBuilder, Error> {
+        let mut request = net::client()?.get(url);
+        if let Some(token) = auth::token(&self.info.host, &["GITLAB_TOKEN"]) {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+        Ok(request)
+    }
+}
+
+/// GitLab 的 commit 转换为 GitHub API 的格式。GitLab 的 commit 中没有作者的用户名。
+fn gitlab_commit(raw_commit: &Value) -> Value {
+    serde_json::json!({
+        "sha": raw_commit["id"],
+        "commit": {
+            "message": raw_commit["message"],
+            "author": {
+                "name": raw_commit["author_name"],
+                "email": raw_commit["author_email"],
+            },
+            "committer": {
+                "name": raw_commit["committer_name"],
+                "email": raw_commit["committer_email"],
+                "date": raw_commit["committed_date"],
+            },
+        },
+        "author": null,
+        "committer": null,
+        "parents": raw_commit["parent_ids"],
+    })
+}
+
+impl Forge for GitLab {
+    fn kind(&self) -> Option<Kind> {
+        Some(Kind::Gitlab)
+    }
+
+    fn web_url(&self) -> Option<String> {
+        Some(self.info.web_url())
+    }
+
+    fn commit_url(&self, sha: &str) -> Option<String> {
+        Some(format!("{}/-/commit/{}", self.web_url()?, sha))
+    }
+
+    fn compare_url(&self, from: &str, to: &str) -> Option<String> {
+        Some(format!("{}/-/compare/{}...{}", self.web_url()?, from, to))
+    }
+
+    fn tree_url(&self, rev: &str) -> Option<String> {
+        Some(format!("{}/-/tree/{}", self.web_url()?, rev))
+    }
+
+    fn pull_request_url(&self, number: u64) -> Option<String> {
+        Some(format!("{}/-/merge_requests/{}", self.web_url()?, number))
+    }
+
+    fn fetch_commits(
+        &self,
+        from: Option<&str>,
+        to: &str,
+        _mail_to_login: &mut HashMap<String, String>,
+    ) -> Result<Option<Vec<FetchedCommit>>, Error> {
+        // ref_name 可以是 `from..to`，和 git log 一样新的在前
+        let rev = match from {
+            Some(from) => format!("{}..{}", from, to),
+            None => to.to_string(),
+        };
+        let url = format!("{}/repository/commits", self.project_url());
+        let request = |page: usize| {
+            Ok(self.get(url.clone())?.query(&[
+                ("ref_name", rev.clone()),
+                ("per_page", "100".to_string()),
+                ("page", page.to_string()),
+            ]))
+        };
+        let raw_commits = match fetch_commit_pages(request, 100, &self.info.host) {
+            Some(raw_commits) => raw_commits,
+            None => return Ok(None),
+        };
+        // 没有用户名，不记录到 mail_to_login，之后由 resolve_username 查询
+        let fetched = raw_commits
+            .iter()
+            .map(|raw_commit| fetched_commit(&gitlab_commit(raw_commit), &mut HashMap::new()))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(fetched))
+    }
+
+    /// 只能找到公开了这个邮箱的用户。
+    fn resolve_username(&self, email: &str) -> Option<String> {
+        let request = self
+            .get(format!("{}/users", self.api))
+            .ok()?
+            .query(&[("search", email)]);
+        let users = get_json(request, &self.info.host).ok()??;
+        match users.as_array()?.as_slice() {
+            [user] => user["username"].as_str().map(|login| login.to_string()),
+            _ => None,
+        }
+    }
+
+    fn cache_key(&self, email: &str) -> String {
+        format!("{} {}", self.info.host, email)
+    }
+
+    /// token 来自 GITLAB_TOKEN 或者 keychain。
+    fn create_release(&self, tag: &str, title: &str, notes: &str) -> Result<String, Error> {
+        let mut request = net::client()?
+            .post(format!("{}/releases", self.project_url()))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(
+                serde_json::json!({
+                    "tag_name": tag,
+                    "name": title,
+                    "description": notes,
+                })
+                .to_string(),
+            );
+        if let Some(token) = auth::token(&self.info.host, &["GITLAB_TOKEN"]) {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+        let response = net::send(request)?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to create the release on {}: HTTP {}",
+                self.info.host,
+                response.status().as_u16()
+            )
+            .into());
+        }
+        let release: Value = serde_json::from_str(response.text()?.as_str())?;
+        Ok(release["_links"]["self"]
+            .as_str()
+            .map(|url| url.to_string())
+            .unwrap_or(format!("{}/-/releases/{}", self.info.web_url(), tag)))
+    }
+}
+
+pub struct Gitea {
+    pub info: RemoteInfo,
+    /// API 的地址，例如 `https://codeberg.org/api/v1`
+    pub api: String,
+}
+
+impl Gitea {
+    /// token 来自 GITEA_TOKEN 或者 keychain。
+    fn get(&self, url: String) -> Result<reqwest::blocking::RequestBuilder, Error> {
+        let mut request = net::client()?.get(url);
+        if let Some(token) = auth::token(&self.info.host, &["GITEA_TOKEN"]) {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        Ok(request)
+    }
+}
+
+impl Forge for Gitea {
+    fn kind(&self) -> Option<Kind> {
+        Some(Kind::Gitea)
+    }
+
+    fn web_url(&self) -> Option<String> {
+        Some(self.info.web_url())
+    }
+
+    /// commit 的格式和 GitHub 相同，作者有账号时带有用户名。
+    fn fetch_commits(
+        &self,
+        from: Option<&str>,
+        to: &str,
+        mail_to_login: &mut HashMap<String, String>,
+    ) -> Result<Option<Vec<FetchedCommit>>, Error> {
+        let url = format!(
+            "{}/repos/{}/{}/commits",
+            self.api, self.info.scope, self.info.repo
+        );
+        let mut query = vec![
+            ("sha", to.to_string()),
+            ("stat", "false".to_string()),
+            ("verification", "false".to_string()),
+            ("files", "false".to_string()),
+            ("limit", "50".to_string()),
+        ];
+        if let Some(from) = from {
+            query.push(("not", from.to_string()));
+        }
+        let request = |page: usize| {
+            Ok(self
+                .get(url.clone())?
+                .query(&query)
+                .query(&[("page", page.to_string())]))
+        };
+        // 默认每页最多 50 个
+        let raw_commits = match fetch_commit_pages(request, 50, &self.info.host) {
+            Some(raw_commits) => raw_commits,
+            None => return Ok(None),
+        };
+        // 旧版本的 Gitea 不支持 not，返回了 from 之前的全部历史
+        if let Some(from) = from {
+            if raw_commits
+                .iter()
+                .any(|raw_commit| raw_commit["sha"].as_str() == Some(from))
+            {
+                log::warn!(
+                    "{} does not support listing a range of commits, using the local history.",
+                    self.info.host
+                );
+                return Ok(None);
+            }
+        }
+        let fetched = raw_commits
+            .iter()
+            .map(|raw_commit| fetched_commit(raw_commit, mail_to_login))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(fetched))
+    }
+
+    /// 邮箱隐藏时找不到用户。
+    fn resolve_username(&self, email: &str) -> Option<String> {
+        let request = self
+            .get(format!("{}/users/search", self.api))
+            .ok()?
+            .query(&[("q", email), ("limit", "2")]);
+        let users = get_json(request, &self.info.host).ok()??;
+        match users["data"].as_array()?.as_slice() {
+            [user] => user["login"].as_str().map(|login| login.to_string()),
+            _ => None,
+        }
+    }
+
+    fn cache_key(&self, email: &str) -> String {
+        format!("{} {}", self.info.host, email)
+    }
+
+    /// token 来自 GITEA_TOKEN 或者 keychain。
+    fn create_release(&self, tag: &str, title: &str, notes: &str) -> Result<String, Error> {
+        let mut request = net::client()?
+            .post(format!(
+                "{}/repos/{}/{}/releases",
+                self.api, self.info.scope, self.info.repo
+            ))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(
+                serde_json::json!({
+                    "tag_name": tag,
+                    "name": title,
+                    "body": notes,
+                })
+                .to_string(),
+            );
+        if let Some(token) = auth::token(&self.info.host, &["GITEA_TOKEN"]) {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        let response = net::send(request)?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Failed to create the release on {}: HTTP {}",
+                self.info.host,
+                response.status().as_u16()
+            )
+            .into());
+        }
+        let release: Value = serde_json::from_str(response.text()?.as_str())?;
+        Ok(release["html_url"]
+            .as_str()
+            .map(|url| url.to_string())
+            .unwrap_or(format!("{}/releases/tag/{}", self.info.web_url(), tag)))
+    }
+}
+
+/// 没有 remote 或者未知的 host。有 URL 时链接使用和 GitHub 相同的格式，用户名通过 ungh 查询，
+/// 因为这样的仓库多数是 GitHub 仓库的镜像。
+pub struct NoForge {
+    pub info: Option<RemoteInfo>,
+}
+
+impl Forge for NoForge {
+    fn kind(&self) -> Option<Kind> {
+        None
+    }
+
+    fn web_url(&self) -> Option<String> {
+        self.info.as_ref().map(|info| info.web_url())
+    }
+
+    fn resolve_username(&self, email: &str) -> Option<String> {
+        fetch_github_username(email).ok()
+    }
+
+    fn create_release(&self, tag: &str, _title: &str, _notes: &str) -> Result<String, Error> {
+        Err(format!(
+            "The remote is not on a known forge, create the release for {} manually.",
+            tag
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+    use crate::{format_changelog_entry, ChangelogStyle};
+
+    // 依次返回 bodies，结束后返回收到的请求行
+    fn serve(bodies: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let api = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 4096];
+                let len = stream.read(&mut buf).unwrap();
+                let request = String::from_utf8_lossy(&buf[..len]).to_string();
+                requests.push(request.lines().next().unwrap_or("").to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (api, server)
+    }
+
+    fn info(host: &str) -> RemoteInfo {
+        RemoteInfo {
+            host: host.to_string(),
+            scope: "a".to_string(),
+            repo: "b".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_gitlab() {
+        let commits = serde_json::json!([
+            {
+                "id": "2222",
+                "message": "feat(cli): add thing\n\nCo-authored-by: B <b@example.com>",
+                "author_name": "A",
+                "author_email": "a@example.com",
+                "committer_name": "A",
+                "committer_email": "a@example.com",
+                "parent_ids": ["1111"],
+            },
+            {
+                "id": "1111",
+                "message": "Merge branch 'x'",
+                "author_name": "A",
+                "author_email": "a@example.com",
+                "committer_name": "A",
+                "committer_email": "a@example.com",
+                "parent_ids": ["0000", "aaaa"],
+            },
+        ]);
+        let users = serde_json::json!([{ "username": "bee" }]);
+        let (api, server) = serve(vec![commits.to_string(), users.to_string()]);
+        let gitlab = GitLab {
+            info: info("gitlab.example.com"),
+            api,
+        };
+        let mut mail_to_login = HashMap::new();
+        let fetched = gitlab
+            .fetch_commits(Some("0000"), "2222", &mut mail_to_login)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.len(), 2);
+        assert_eq!(fetched[0].sha, "2222");
+        assert!(!fetched[0].is_merge && fetched[1].is_merge);
+        match &fetched[0].parsed {
+            Parsed::Conventional(commit, _) => {
+                assert_eq!(commit.scope, "cli");
+                assert_eq!(commit.authors.len(), 2);
+            }
+            Parsed::Uncategorized(_) => panic!("not parsed"),
+        }
+        // GitLab 的 commit 中没有用户名，之后通过 resolve_username 查询
+        assert!(mail_to_login.is_empty());
+        assert_eq!(
+            gitlab.resolve_username("b@example.com").as_deref(),
+            Some("bee")
+        );
+        assert_eq!(
+            gitlab.cache_key("b@example.com"),
+            "gitlab.example.com b@example.com"
+        );
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with(
+            "GET /projects/a%2Fb/repository/commits?ref_name=0000..2222&per_page=100&page=1 "
+        ));
+        assert!(requests[1].starts_with("GET /users?search=b%40example.com "));
+    }
+
+    #[test]
+    fn test_gitea() {
+        let commit = |sha: &str, message: &str| {
+            serde_json::json!({
+                "sha": sha,
+                "commit": {
+                    "message": message,
+                    "author": { "name": "A", "email": "a@example.com" },
+                    "committer": { "name": "A", "email": "a@example.com" },
+                },
+                "author": { "login": "ay" },
+                "committer": { "login": "ay" },
+                "parents": [{ "sha": "0000" }],
+            })
+        };
+        let users = serde_json::json!({ "data": [{ "login": "bee" }], "ok": true });
+        let (api, server) = serve(vec![
+            serde_json::json!([commit("2222", "fix: thing")]).to_string(),
+            users.to_string(),
+            // 不支持 not 时返回了 from 本身
+            serde_json::json!([commit("2222", "fix: thing"), commit("0000", "feat: init")])
+                .to_string(),
+        ]);
+        let gitea = Gitea {
+            info: info("codeberg.org"),
+            api,
+        };
+        let mut mail_to_login = HashMap::new();
+        let fetched = gitea
+            .fetch_commits(Some("0000"), "2222", &mut mail_to_login)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert!(matches!(fetched[0].parsed, Parsed::Conventional(..)));
+        assert_eq!(
+            mail_to_login.get("a@example.com").map(String::as_str),
+            Some("ay")
+        );
+        assert_eq!(
+            gitea.resolve_username("b@example.com").as_deref(),
+            Some("bee")
+        );
+        assert!(gitea
+            .fetch_commits(Some("0000"), "2222", &mut mail_to_login)
+            .unwrap()
+            .is_none());
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /repos/a/b/commits?sha=2222&"));
+        assert!(requests[0].contains("&not=0000&page=1 "));
+        assert!(requests[1].starts_with("GET /users/search?q=b%40example.com&limit=2 "));
+    }
+
+    #[test]
+    fn test_forge() {
+        let info = |host: &str| RemoteInfo {
+            host: host.to_string(),
+            scope: "a".to_string(),
+            repo: "b".to_string(),
+        };
+        let gitlab = detect(Some(info("gitlab.com")));
+        assert_eq!(gitlab.kind(), Some(Kind::Gitlab));
+        assert_eq!(
+            gitlab.compare_url("v0.1.0", "v0.2.0").unwrap(),
+            "https://gitlab.com/a/b/-/compare/v0.1.0...v0.2.0"
+        );
+        assert_eq!(
+            gitlab.pull_request_url(3).unwrap(),
+            "https://gitlab.com/a/b/-/merge_requests/3"
+        );
+        let gitea = detect(Some(info("codeberg.org")));
+        assert_eq!(gitea.kind(), Some(Kind::Gitea));
+        assert_eq!(
+            gitea.commit_url("abc").unwrap(),
+            "https://codeberg.org/a/b/commit/abc"
+        );
+        let unknown = detect(Some(info("git.example.com")));
+        assert_eq!(unknown.kind(), None);
+        assert!(unknown.create_release("v0.1.0", "", "").is_err());
+        let none = detect(None);
+        assert_eq!(none.commit_url("abc"), None);
+        let commit = Commit::new(
+            "0".repeat(40),
+            "fix".to_string(),
+            "".to_string(),
+            "x (#3)".to_string(),
+            false,
+            vec![],
+            vec![],
+        );
+        let entry =
+            format_changelog_entry(&commit, none.as_ref(), &ChangelogStyle::default(), true);
+        assert!(!entry.contains("]("));
+    }
+
+    #[test]
+    fn test_forge_hosts() {
+        let hosts = Hosts(HashMap::from([
+            (
+                "git.mycorp.com".to_string(),
+                Host {
+                    forge: Some(Kind::Github),
+                    api: Some("https://git.mycorp.com/api/v3/".to_string()),
+                    web: None,
+                },
+            ),
+            (
+                "ghe".to_string(),
+                Host {
+                    web: Some("https://github.mycorp.com".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ]));
+        assert_eq!(hosts.kind("git.mycorp.com"), Some(Kind::Github));
+        assert_eq!(
+            hosts.api_url("git.mycorp.com"),
+            "https://git.mycorp.com/api/v3"
+        );
+        assert_eq!(hosts.kind("git.example.com"), None);
+        assert_eq!(hosts.api_url("github.com"), "https://api.github.com");
+        assert_eq!(hosts.api_url("gitlab.com"), "https://gitlab.com/api/v4");
+        assert_eq!(hosts.web_url("ghe"), "https://github.mycorp.com");
+        assert_eq!(hosts.gh_host("ghe"), "github.mycorp.com");
+        assert_eq!(hosts.gh_host("github.com"), "github.com");
+    }
+}
@@ -0,0 +1,107 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use regex::Regex;
+
+/// conventional commit 标题的默认格式
+pub const DEFAULT_PATTERN: &str = r#"(?P<emoji>:.+:|(\u{1F300}-\u{1F3FF})|(\u{1F400}-\u{1F64F})|[\u{2600}-\u{2B55}])?( *)?(?P<type>[a-z]+)(\((?P<scope>.+)\))?(?P<breaking>!)?: (?P<description>.+)"#;
+
+// 启动时由 tgit.toml 配置，未配置时使用默认的规则
+static GRAMMAR: OnceLock<Grammar> = OnceLock::new();
+
+/// 解析 commit 标题的规则：标题的正则和类型的别名。
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    pattern: Regex,
+    /// 例如 `bugfix` → `fix`
+    aliases: HashMap<String, String>,
+}
+
+impl Default for Grammar {
+    fn default() -> Self {
+        Self {
+            pattern: Regex::new(DEFAULT_PATTERN).unwrap(),
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+impl Grammar {
+    /// pattern 必须包含 `type` 和 `description` 两个命名分组，`scope`、`breaking` 和 `emoji` 是可选的。
+    pub fn new(
+        pattern: Option<&str>,
+        aliases: HashMap<String, String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let pattern = match pattern {
+            Some(pattern) => {
+                Regex::new(pattern).map_err(|err| format!("Invalid commit_pattern: {}", err))?
+            }
+            None => Regex::new(DEFAULT_PATTERN).unwrap(),
+        };
+        for group in ["type", "description"] {
+            if !pattern.capture_names().flatten().any(|name| name == group) {
+                return Err(
+                    format!("commit_pattern must have a `(?P<{}>...)` group.", group).into(),
+                );
+            }
+        }
+        Ok(Self { pattern, aliases })
+    }
+
+    /// 返回 (emoji, scope, description, type, is_breaking)，类型已经替换为别名对应的类型。
+    pub fn parse(&self, subject: &str) -> Option<(String, String, String, String, bool)> {
+        let captures = self.pattern.captures(subject)?;
+        let group = |name: &str| captures.name(name).map_or("", |m| m.as_str()).to_string();
+        let type_ = group("type");
+        let type_ = self.aliases.get(type_.as_str()).cloned().unwrap_or(type_);
+        let is_breaking = !group("breaking").is_empty();
+        Some((
+            group("emoji"),
+            group("scope"),
+            group("description"),
+            type_,
+            is_breaking,
+        ))
+    }
+}
+
+pub fn configure(grammar: Grammar) {
+    let _ = GRAMMAR.set(grammar);
+}
+
+pub fn get() -> &'static Grammar {
+    GRAMMAR.get_or_init(Grammar::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grammar() {
+        let aliases = HashMap::from([("bugfix".to_string(), "fix".to_string())]);
+        let grammar = Grammar::new(None, aliases.clone()).unwrap();
+        assert_eq!(
+            grammar.parse("bugfix(parser)!: handle tabs"),
+            Some((
+                "".to_string(),
+                "parser".to_string(),
+                "handle tabs".to_string(),
+                "fix".to_string(),
+                true
+            ))
+        );
+        // 例如 `[Feature] add thing`
+        let grammar = Grammar::new(
+            Some(r"^\[(?P<type>\w+)\] (?P<description>.+)$"),
+            HashMap::from([("Feature".to_string(), "feat".to_string())]),
+        )
+        .unwrap();
+        let (_, _, description, type_, _) = grammar.parse("[Feature] add thing").unwrap();
+        assert_eq!(
+            (description.as_str(), type_.as_str()),
+            ("add thing", "feat")
+        );
+        assert!(grammar.parse("feat: add thing").is_none());
+        assert!(Grammar::new(Some(r"^(?P<type>\w+)"), aliases).is_err());
+    }
+}
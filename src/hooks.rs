@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+// 用于识别由 tgit 写入的 hook，其他 hook 不会被覆盖
+const MARKER: &str = "# Installed by tgit.";
+
+const COMMIT_MSG_HOOK: &str = r#"#!/bin/sh
+# Installed by tgit.
+exec tgit lint --message-file "$1"
+"#;
+
+const PRE_PUSH_HOOK: &str = r#"#!/bin/sh
+# Installed by tgit.
+latest_tag=$(git describe --tags --abbrev=0 2>/dev/null)
+if [ -n "$latest_tag" ]; then
+    range="$latest_tag..HEAD"
+else
+    range="HEAD"
+fi
+if git log "$range" --format=%B | grep -Eq '^(:[a-z_]+: *)?[a-z]+(\(.+\))?!: |^BREAKING[ -]CHANGE:'; then
+    echo "pre-push: there are unreleased breaking changes since ${latest_tag:-the first commit}." >&2
+fi
+exit 0
+"#;
+
+pub fn install(path: &Path, pre_push: bool, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = git2::Repository::open(path)?;
+    let hook_path = install_hook(&repo, "commit-msg", COMMIT_MSG_HOOK, force)?;
+    log::info!("Wrote {}", hook_path.display());
+    if pre_push {
+        let hook_path = install_hook(&repo, "pre-push", PRE_PUSH_HOOK, force)?;
+        log::info!("Wrote {}", hook_path.display());
+    }
+    Ok(())
+}
+
+pub fn install_commit_msg(repo: &git2::Repository) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    install_hook(repo, "commit-msg", COMMIT_MSG_HOOK, false)
+}
+
+/// hook 目录和 git 一样优先使用 core.hooksPath。
+fn hooks_dir(repo: &git2::Repository) -> PathBuf {
+    let hooks_path = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok());
+    match (hooks_path, repo.workdir()) {
+        (Some(hooks_path), Some(workdir)) if hooks_path.is_relative() => workdir.join(hooks_path),
+        (Some(hooks_path), _) => hooks_path,
+        (None, _) => repo.path().join("hooks"),
+    }
+}
+
+fn install_hook(
+    repo: &git2::Repository,
+    name: &str,
+    content: &str,
+    force: bool,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let hooks_dir = hooks_dir(repo);
+    std::fs::create_dir_all(&hooks_dir)?;
+    let hook_path = hooks_dir.join(name);
+    if hook_path.exists() && !force {
+        let existing = std::fs::read_to_string(&hook_path).unwrap_or_default();
+        if !existing.contains(MARKER) {
+            return Err(format!(
+                "{} already exists and was not installed by tgit, use --force to overwrite it.",
+                hook_path.display()
+            )
+            .into());
+        }
+    }
+    crate::files::write_atomic(&hook_path, content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(hook_path)
+}
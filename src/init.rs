@@ -0,0 +1,61 @@
+use inquire::{Confirm, MultiSelect, Select, Text};
+
+use crate::{
+    config::{Config, CONFIG_FILE, DEFAULT_TYPES},
+    hooks,
+};
+
+pub fn init(path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = git2::Repository::open(path)?;
+    let workdir = repo.workdir().ok_or("The repository is bare.")?;
+
+    if workdir.join(CONFIG_FILE).exists() {
+        let overwrite =
+            Confirm::new(format!("{} already exists. Overwrite it?", CONFIG_FILE).as_str())
+                .with_default(false)
+                .prompt()?;
+        if !overwrite {
+            return Ok(());
+        }
+    }
+
+    let prefix = Text::new("Version tag prefix:")
+        .with_default("v")
+        .prompt()?;
+    let all_types = DEFAULT_TYPES.to_vec();
+    let default_indexes = (0..all_types.len()).collect::<Vec<_>>();
+    let types = MultiSelect::new("Commit types used in this repository:", all_types)
+        .with_default(&default_indexes)
+        .prompt()?;
+    let emoji = Confirm::new("Use emoji in release commits?")
+        .with_default(false)
+        .prompt()?;
+    let changelog = Text::new("Changelog path:")
+        .with_default("CHANGELOG.md")
+        .prompt()?;
+    let token = Select::new(
+        "Where should the forge API token come from?",
+        vec!["gh", "env", "none"],
+    )
+    .prompt()?;
+
+    let config = Config {
+        prefix: Some(prefix),
+        types: Some(types.iter().map(|type_| type_.to_string()).collect()),
+        emoji: Some(emoji),
+        changelog: Some(changelog),
+        token: Some(token.to_string()),
+        ..Default::default()
+    };
+    config.save(workdir)?;
+    log::info!("Wrote {}", workdir.join(CONFIG_FILE).display());
+
+    let install_hook = Confirm::new("Install a commit-msg hook to check commit messages?")
+        .with_default(true)
+        .prompt()?;
+    if install_hook {
+        let hook_path = hooks::install_commit_msg(&repo)?;
+        log::info!("Wrote {}", hook_path.display());
+    }
+    Ok(())
+}
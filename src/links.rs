@@ -0,0 +1,173 @@
+use git2::Repository;
+
+use crate::{config::Config, get_remote, get_remote_info, net, Options};
+
+/// 检查 changelog 中的链接。本仓库的 commit、compare 和 tree 链接使用本地的 ref 检查，其他链接发送 HTTP 请求。
+pub fn check_links(
+    args: &Options,
+    file: Option<&std::path::Path>,
+    offline: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(args.path.as_path())?;
+    let file = match file {
+        Some(file) => file.to_path_buf(),
+        None => args.path.join(config.changelog_path()),
+    };
+    let content = std::fs::read_to_string(&file)
+        .map_err(|err| format!("Failed to read {}: {}", file.display(), err))?;
+    let repo = Repository::open(args.path.as_path()).ok();
+    let remote = repo
+        .as_ref()
+        .map(|repo| get_remote(repo, args))
+        .unwrap_or("origin".to_string());
+    let repo_url = repo
+        .as_ref()
+        .and_then(|repo| get_remote_info(repo, remote.as_str()))
+        .map(|info| info.web_url());
+
+    let links = extract_links(content.as_str());
+    let mut broken = Vec::<(usize, String, String)>::new();
+    let mut remote_links = Vec::<(usize, String)>::new();
+    for (line, url) in links.iter() {
+        let local = match (&repo, &repo_url) {
+            (Some(repo), Some(repo_url)) => check_local(repo, repo_url, remote.as_str(), url),
+            _ => None,
+        };
+        match local {
+            Some(Ok(())) => {}
+            Some(Err(reason)) => broken.push((*line, url.to_string(), reason)),
+            None => remote_links.push((*line, url.to_string())),
+        }
+    }
+
+    let mut skipped = 0;
+    if offline || net::expired() {
+        skipped = remote_links.len();
+    } else if !remote_links.is_empty() {
+        let client = net::client()?;
+        let results = net::parallel(remote_links, |(line, url)| {
+            let result = check_url(client, url.as_str());
+            (line, url, result)
+        });
+        for (line, url, result) in results {
+            if let Err(reason) = result {
+                broken.push((line, url, reason));
+            }
+        }
+    }
+
+    broken.sort();
+    for (line, url, reason) in &broken {
+        println!("{}:{}: {} ({})", file.display(), line, url, reason);
+    }
+    println!(
+        "Checked {} links: {} broken, {} skipped.",
+        links.len(),
+        broken.len(),
+        skipped
+    );
+    if !broken.is_empty() {
+        return Err(format!("{} broken links in {}.", broken.len(), file.display()).into());
+    }
+    Ok(())
+}
+
+// markdown 中的链接和所在的行号
+pub fn extract_links(content: &str) -> Vec<(usize, String)> {
+    let link_re = regex!(r"\]\((?P<url>https?://[^)\s]+)\)");
+    let mut links = Vec::<(usize, String)>::new();
+    for (i, line) in content.lines().enumerate() {
+        for captures in link_re.captures_iter(line) {
+            links.push((i + 1, captures["url"].to_string()));
+        }
+    }
+    links
+}
+
+// 本仓库的 commit、compare 和 tree 链接只需要对应的 ref 存在，返回 None 时需要发送请求检查
+pub fn check_local(
+    repo: &Repository,
+    repo_url: &str,
+    remote: &str,
+    url: &str,
+) -> Option<Result<(), String>> {
+    let path = url.strip_prefix(repo_url)?;
+    let revs = if let Some(sha) = path.strip_prefix("/commit/") {
+        vec![sha]
+    } else if let Some(range) = path.strip_prefix("/compare/") {
+        let (from, to) = range.split_once("...")?;
+        vec![from, to]
+    } else if let Some(rev) = path.strip_prefix("/tree/") {
+        vec![rev]
+    } else {
+        return None;
+    };
+    for rev in revs {
+        // compare 链接中的默认分支在本地可能只有 remote 的分支
+        let exists = repo.revparse_single(rev).is_ok()
+            || repo
+                .revparse_single(format!("{}/{}", remote, rev).as_str())
+                .is_ok();
+        if !exists {
+            return Some(Err(format!("`{}` does not exist locally", rev)));
+        }
+    }
+    Some(Ok(()))
+}
+
+fn check_url(client: &reqwest::blocking::Client, url: &str) -> Result<(), String> {
+    let send = |method: reqwest::Method| {
+        net::send(
+            client
+                .request(method, url)
+                .header(reqwest::header::USER_AGENT, "tgit"),
+        )
+        .map_err(|err| err.to_string())
+    };
+    let mut response = send(reqwest::Method::HEAD)?;
+    // 有些服务器不支持 HEAD
+    if response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        response = send(reqwest::Method::GET)?;
+    }
+    let status = response.status();
+    if status.is_success() || status.is_redirection() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", status.as_u16()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Repository;
+
+    use super::*;
+    use crate::gitt_tests::fixture;
+
+    #[test]
+    fn test_check_links() {
+        let dir = fixture("with_tag");
+        let repo = Repository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap().target().unwrap();
+        let repo_url = "https://github.com/a/b";
+        let content = format!(
+            "## v0.1.0\n\n[compare changes]({0}/compare/v0.0.1...v0.1.0)\n- init ([abc]({0}/commit/{1})) ([#1]({0}/pull/1))\n",
+            repo_url, head
+        );
+        let links = extract_links(content.as_str());
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[1], (4, format!("{}/commit/{}", repo_url, head)));
+        let results = links
+            .iter()
+            .map(|(_, url)| check_local(&repo, repo_url, "origin", url))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![
+                Some(Err("`v0.0.1` does not exist locally".to_string())),
+                Some(Ok(())),
+                None
+            ]
+        );
+    }
+}
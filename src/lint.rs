@@ -0,0 +1,178 @@
+use crate::{
+    config::Config, exit, get_commit_tag_map, get_from_commit, list_tags, open_repo,
+    parse_first_line, push_range, resolve_commit, Options,
+};
+
+const HEADER_MAX_LENGTH: usize = 100;
+
+/// commit message 中不符合规范的一处。
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub line: usize,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+pub fn lint(
+    args: &Options,
+    message_file: Option<&std::path::Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(args.path.as_path())?;
+    let types = config.types();
+    let scopes = config.allowed_scopes.clone().unwrap_or_default();
+
+    // commit-msg hook 中只检查即将提交的 message
+    if let Some(message_file) = message_file {
+        let message = std::fs::read_to_string(message_file)?;
+        let violations = lint_message(message.as_str(), &types, &scopes);
+        for violation in &violations {
+            println!(
+                "{}:{}: {}: {}",
+                message_file.display(),
+                violation.line,
+                violation.rule,
+                violation.message
+            );
+        }
+        if !violations.is_empty() {
+            return Err(exit::fail(
+                exit::LINT,
+                "The commit message does not follow the conventional commit format.",
+            ));
+        }
+        return Ok(());
+    }
+
+    let repo = open_repo(args.path.as_path())?;
+    let tags = list_tags(&repo, args, &config)?;
+    let (c2t, _) = get_commit_tag_map(&repo, &tags);
+    let to_commit = resolve_commit(&repo, args.to.as_deref().unwrap_or("HEAD"))?;
+    let from_commit = get_from_commit(&repo, args.from.clone(), &to_commit, &c2t)?;
+    let mut revwalk = repo.revwalk()?;
+    push_range(&mut revwalk, from_commit.as_ref(), &to_commit)?;
+    let mut invalid = 0;
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        let violations = lint_message(commit.message().unwrap_or(""), &types, &scopes);
+        if violations.is_empty() {
+            continue;
+        }
+        invalid += 1;
+        let hash = commit.id().to_string().chars().take(7).collect::<String>();
+        println!("{} {}", hash, commit.summary().unwrap_or(""));
+        for violation in &violations {
+            println!(
+                "  line {}: {}: {}",
+                violation.line, violation.rule, violation.message
+            );
+        }
+    }
+    if invalid > 0 {
+        return Err(exit::fail(
+            exit::LINT,
+            format!(
+                "{} commit message(s) do not follow the conventional commit format.",
+                invalid
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// scopes 为空时不检查 scope。
+pub fn lint_message(message: &str, types: &[String], scopes: &[String]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    // 忽略 git 注释，和 commit-msg hook 看到的内容保持一致
+    let lines = message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    let header = lines.first().copied().unwrap_or("");
+    // git 自动生成的 merge / revert message 不检查
+    if header.starts_with("Merge ") || header.starts_with("Revert \"") {
+        return violations;
+    }
+    if header.trim().is_empty() {
+        violations.push(Violation {
+            line: 1,
+            rule: "header-empty",
+            message: "The header must not be empty.".to_string(),
+        });
+        return violations;
+    }
+    match parse_first_line(header) {
+        Some((_, scope, description, type_, _)) => {
+            if !types.contains(&type_) {
+                violations.push(Violation {
+                    line: 1,
+                    rule: "type-enum",
+                    message: format!("`{}` is not one of: {}.", type_, types.join(", ")),
+                });
+            }
+            if !scope.is_empty() && !scopes.is_empty() && !scopes.contains(&scope) {
+                violations.push(Violation {
+                    line: 1,
+                    rule: "scope-enum",
+                    message: format!("`{}` is not one of: {}.", scope, scopes.join(", ")),
+                });
+            }
+            if description.trim().is_empty() {
+                violations.push(Violation {
+                    line: 1,
+                    rule: "subject-empty",
+                    message: "The description must not be empty.".to_string(),
+                });
+            }
+        }
+        None => violations.push(Violation {
+            line: 1,
+            rule: "header-format",
+            message: "The header must look like `type(scope)!: description`.".to_string(),
+        }),
+    }
+    if header.chars().count() > HEADER_MAX_LENGTH {
+        violations.push(Violation {
+            line: 1,
+            rule: "header-max-length",
+            message: format!(
+                "The header must not be longer than {} characters.",
+                HEADER_MAX_LENGTH
+            ),
+        });
+    }
+    if lines.len() > 1 && !lines[1].trim().is_empty() {
+        violations.push(Violation {
+            line: 2,
+            rule: "body-leading-blank",
+            message: "The body must be separated from the header by a blank line.".to_string(),
+        });
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_message() {
+        let types = Config::default().types();
+        let scopes = vec!["cli".to_string()];
+        assert!(lint_message("feat(cli): add lint\n\nbody", &types, &scopes).is_empty());
+        assert!(lint_message("Merge branch 'main'", &types, &scopes).is_empty());
+        assert!(lint_message("fix(parser): x", &types, &[]).is_empty());
+        let rules = |message: &str| {
+            lint_message(message, &types, &scopes)
+                .iter()
+                .map(|violation| violation.rule)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rules("update readme"), vec!["header-format"]);
+        assert_eq!(
+            rules("feature: add lint\nbody"),
+            vec!["type-enum", "body-leading-blank"]
+        );
+        assert_eq!(rules("# only a comment\n"), vec!["header-empty"]);
+        assert_eq!(rules("fix(parser): x"), vec!["scope-enum"]);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use crate::{files, parse_tag_version, split_changelog_sections};
+
+/// 作为 git merge driver 使用：`tgit merge-changelog %O %A %B`，结果写回 ours。
+pub fn merge_changelog_files(
+    base: &std::path::Path,
+    ours: &std::path::Path,
+    theirs: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let read = |path: &std::path::Path| {
+        files::read(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))
+    };
+    // 新增的文件没有 base
+    let (base_content, _) = read(base).unwrap_or((String::new(), files::LineEnding::Lf));
+    let (ours_content, ending) = read(ours)?;
+    let (theirs_content, _) = read(theirs)?;
+    let merged = merge_changelogs(
+        base_content.as_str(),
+        ours_content.as_str(),
+        theirs_content.as_str(),
+    );
+    files::write(ours, merged.as_str(), ending)?;
+    Ok(())
+}
+
+/// 按照版本合并两边对 changelog 的修改。
+/// 只有一边修改的部分使用修改后的内容，两边都修改时合并两边的行；版本按照从新到旧排列，Unreleased 在最前面。
+pub fn merge_changelogs(base: &str, ours: &str, theirs: &str) -> String {
+    let (base_intro, base_sections) = split_changelog_sections(base);
+    let (ours_intro, ours_sections) = split_changelog_sections(ours);
+    let (theirs_intro, theirs_sections) = split_changelog_sections(theirs);
+    let find = |sections: &[(String, String)], title: &str| {
+        sections
+            .iter()
+            .find(|(other, _)| other == title)
+            .map(|(_, text)| text.clone())
+    };
+
+    let intro = merge_text(&base_intro, &ours_intro, &theirs_intro);
+    let mut titles = ours_sections
+        .iter()
+        .map(|(title, _)| title.clone())
+        .collect::<Vec<_>>();
+    for (title, _) in &theirs_sections {
+        if !titles.contains(title) {
+            titles.push(title.clone());
+        }
+    }
+    let mut sections = Vec::<(String, String)>::new();
+    for title in titles {
+        let base_text = find(&base_sections, &title);
+        let text = match (find(&ours_sections, &title), find(&theirs_sections, &title)) {
+            (Some(ours), Some(theirs)) => {
+                merge_text(base_text.as_deref().unwrap_or(""), &ours, &theirs)
+            }
+            // 一边删除了这个版本，另一边没有修改时删除
+            (Some(text), None) | (None, Some(text)) if base_text.as_ref() == Some(&text) => {
+                continue
+            }
+            (Some(text), None) | (None, Some(text)) => text,
+            (None, None) => continue,
+        };
+        sections.push((title, text));
+    }
+    let is_unreleased = |title: &str| title.to_lowercase().starts_with("unreleased");
+    sections.sort_by(|(a, _), (b, _)| {
+        is_unreleased(b)
+            .cmp(&is_unreleased(a))
+            .then(parse_tag_version(b, "").cmp(&parse_tag_version(a, "")))
+    });
+
+    let mut parts = Vec::<String>::new();
+    if !intro.trim().is_empty() {
+        parts.push(intro);
+    }
+    parts.extend(sections.into_iter().map(|(_, text)| text));
+    let mut content = parts
+        .iter()
+        .map(|part| part.trim())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    content.push('\n');
+    content
+}
+
+// 三方合并一段文本。两边都修改时，以 ours 为基础，去掉 theirs 删除的行，并把 theirs 新增的行插在它在 theirs 中的前一行和 ours 在那里新增的行后面
+fn merge_text(base: &str, ours: &str, theirs: &str) -> String {
+    if ours == theirs || theirs == base {
+        return ours.to_string();
+    }
+    if ours == base {
+        return theirs.to_string();
+    }
+    let base_lines = base.lines().collect::<HashSet<_>>();
+    let theirs_lines = theirs.lines().collect::<HashSet<_>>();
+    let mut lines = ours
+        .lines()
+        .filter(|line| !base_lines.contains(line) || theirs_lines.contains(line))
+        .collect::<Vec<_>>();
+    let mut previous: Option<&str> = None;
+    for line in theirs.lines() {
+        if !base_lines.contains(line) && !lines.contains(&line) {
+            let mut index = previous
+                .and_then(|previous| lines.iter().position(|other| *other == previous))
+                .map_or(lines.len(), |index| index + 1);
+            // 放在 ours 在同一位置新增的行后面
+            while index < lines.len()
+                && !base_lines.contains(lines[index])
+                && !lines[index].trim().is_empty()
+            {
+                index += 1;
+            }
+            lines.insert(index, line);
+        }
+        if !line.trim().is_empty() {
+            previous = Some(line);
+        }
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_changelogs() {
+        let base = "# Changelog\n\n## Unreleased\n\n- a\n\n## v1.0.0\n\n- init\n";
+        // main 上增加了 Unreleased 的条目，release 分支发布了 v1.0.1
+        let ours = "# Changelog\n\n## Unreleased\n\n- a\n- b\n\n## v1.0.0\n\n- init\n";
+        let theirs =
+            "# Changelog\n\n## Unreleased\n\n- a\n- c\n\n## v1.0.1\n\n- fix\n\n## v1.0.0\n\n- init\n";
+        assert_eq!(
+            merge_changelogs(base, ours, theirs),
+            "# Changelog\n\n## Unreleased\n\n- a\n- b\n- c\n\n## v1.0.1\n\n- fix\n\n## v1.0.0\n\n- init\n"
+        );
+    }
+}
@@ -0,0 +1,493 @@
+use std::{
+    io::Read,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// 并行请求时同时运行的最大数量。
+pub const CONCURRENCY: usize = 8;
+
+/// tgit.toml 中的 `[network]`，用于公司的代理和自建 forge 的证书。
+/// 没有配置 proxy 时使用 HTTP_PROXY、HTTPS_PROXY 和 NO_PROXY 环境变量。
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Network {
+    /// 所有请求使用的代理，例如 `http://proxy.mycorp.com:8080`。
+    pub proxy: Option<String>,
+    /// 不经过 proxy 的 host，逗号分隔，例如 `localhost,.mycorp.com`。
+    pub no_proxy: Option<String>,
+    /// 额外信任的 CA 证书（PEM，可以包含多个），例如解密 TLS 的代理的根证书。
+    /// gh 通过 SSL_CERT_FILE 使用它，只信任其中的证书；git push 使用 git 的配置。
+    pub ca_bundle: Option<String>,
+    /// 不验证 TLS 证书，只应在无法获得 CA 证书时使用。gh 不支持这个选项。
+    pub insecure: Option<bool>,
+}
+
+// 启动时由 tgit.toml 的 `[network]` 和 --insecure 配置
+static NETWORK: OnceLock<Network> = OnceLock::new();
+
+pub fn configure(network: Network) {
+    let _ = NETWORK.set(network);
+}
+
+pub fn network() -> &'static Network {
+    NETWORK.get_or_init(Network::default)
+}
+
+// 所有网络操作共享的截止时间，未设置 --timeout 时为 None
+static DEADLINE: OnceLock<Option<Instant>> = OnceLock::new();
+static WARNED: AtomicBool = AtomicBool::new(false);
+static WARNED_RATE_LIMIT: AtomicBool = AtomicBool::new(false);
+
+/// 限流或者服务端错误时最多重试的次数。
+const RETRIES: u32 = 3;
+// 等待的时间超过它时不再重试，例如 GitHub 的限流要等到一小时后才重置
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+pub fn set_timeout(seconds: Option<u64>) {
+    let _ = DEADLINE.set(seconds.map(|seconds| Instant::now() + Duration::from_secs(seconds)));
+}
+
+/// 剩余的网络时间预算，没有限制时返回 None。
+pub fn remaining() -> Option<Duration> {
+    DEADLINE
+        .get()
+        .copied()
+        .flatten()
+        .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+pub fn expired() -> bool {
+    remaining().is_some_and(|remaining| remaining.is_zero())
+}
+
+// 每次运行只提示一次
+pub fn warn_offline(what: &str) {
+    if WARNED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    log::warn!(
+        "network timeout exceeded while {}, continuing offline.",
+        what
+    );
+}
+
+/// 被限流时的错误信息。
+pub fn rate_limit_error(api: &str) -> String {
+    format!(
+        "Rate limited by {}, try again later or with a token (set GITHUB_TOKEN or run `tgit auth login github`).",
+        api
+    )
+}
+
+// 每次运行只提示一次
+pub fn warn_rate_limited(api: &str) {
+    if WARNED_RATE_LIMIT.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    log::warn!("{}", rate_limit_error(api));
+}
+
+// 第 attempt 次重试前等待的时间：1s、2s、4s
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt)
+}
+
+// 等待 delay 后重试。等待太久或者超出时间预算时返回 false
+fn wait(delay: Duration) -> bool {
+    if delay > MAX_WAIT || remaining().is_some_and(|remaining| remaining <= delay) {
+        return false;
+    }
+    log::debug!("retrying in {:?}", delay);
+    std::thread::sleep(delay);
+    true
+}
+
+/// GitHub CLI。`[network]` 的代理和证书通过 gh 支持的环境变量传递。
+pub fn gh() -> Command {
+    // Windows 上通过 scoop、npm 等安装的 gh 可能是 `gh.cmd`。直接运行而不是通过 `cmd /C`，
+    // 这样标准库会转义参数，changelog 中的 `&`、`|` 之类的字符不会被 cmd 解释
+    let program = std::env::var_os("PATH")
+        .filter(|_| cfg!(windows))
+        .and_then(|paths| find_program(&paths, &["gh.exe", "gh.cmd", "gh.bat"]))
+        .unwrap_or(PathBuf::from("gh"));
+    let mut command = Command::new(program);
+    let network = network();
+    if let Some(proxy) = &network.proxy {
+        command.env("HTTPS_PROXY", proxy).env("HTTP_PROXY", proxy);
+    }
+    if let Some(no_proxy) = &network.no_proxy {
+        command.env("NO_PROXY", no_proxy);
+    }
+    if let Some(ca_bundle) = &network.ca_bundle {
+        command.env("SSL_CERT_FILE", ca_bundle);
+    }
+    command
+}
+
+// PATH 中第一个存在的程序，同一个目录中按照 names 的顺序
+fn find_program(paths: &std::ffi::OsStr, names: &[&str]) -> Option<PathBuf> {
+    std::env::split_paths(paths).find_map(|dir| {
+        names
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|program| program.is_file())
+    })
+}
+
+/// 在剩余的时间预算内运行命令。超时后会杀掉子进程并返回 None。
+pub fn output(command: &mut Command) -> std::io::Result<Option<Output>> {
+    log::trace!("running {:?}", command);
+    let remaining = match remaining() {
+        Some(remaining) => remaining,
+        None => return command.output().map(Some),
+    };
+    if remaining.is_zero() {
+        return Ok(None);
+    }
+    let deadline = Instant::now() + remaining;
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // 需要在等待的同时读取输出，否则输出过多时子进程会阻塞在管道上
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(Output {
+                status,
+                stdout: stdout_reader.join().unwrap_or_default(),
+                stderr: stderr_reader.join().unwrap_or_default(),
+            }));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// gh api 失败的原因是否是限流。
+pub fn gh_rate_limited(stderr: &[u8]) -> bool {
+    let stderr = String::from_utf8_lossy(stderr).to_lowercase();
+    stderr.contains("rate limit") || stderr.contains("http 429")
+}
+
+/// gh api 的错误信息，限流时提示使用 token。
+pub fn gh_error(stderr: &[u8]) -> String {
+    if gh_rate_limited(stderr) {
+        return rate_limit_error("the GitHub API");
+    }
+    String::from_utf8_lossy(stderr).trim().to_string()
+}
+
+/// gh api 失败后是否可以重试：服务端错误和 secondary rate limit。
+pub fn gh_retryable(stderr: &[u8]) -> bool {
+    let stderr = String::from_utf8_lossy(stderr).to_lowercase();
+    stderr.contains("secondary rate limit")
+        || stderr.contains("http 429")
+        || regex!(r"http 5\d\d").is_match(stderr.as_str())
+}
+
+/// 运行 gh api，服务端错误和 secondary rate limit 时按指数退避重试。
+/// 主限流要等到一小时后才重置，不会重试。
+pub fn gh_output(command: &mut Command) -> std::io::Result<Option<Output>> {
+    let mut attempt = 0;
+    loop {
+        let output = match output(command)? {
+            Some(output) => output,
+            None => return Ok(None),
+        };
+        if output.status.success()
+            || !gh_retryable(&output.stderr)
+            || attempt == RETRIES
+            || !wait(backoff(attempt))
+        {
+            return Ok(Some(output));
+        }
+        attempt += 1;
+    }
+}
+
+static CLIENT: OnceLock<reqwest::blocking::Client> = OnceLock::new();
+
+/// 所有请求共享的 client，复用连接。
+pub fn client() -> Result<&'static reqwest::blocking::Client, Box<dyn std::error::Error>> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = build_client(network())?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+/// 按照 `[network]` 配置代理和证书。
+pub fn build_client(
+    network: &Network,
+) -> Result<reqwest::blocking::Client, Box<dyn std::error::Error>> {
+    let mut builder = reqwest::blocking::Client::builder();
+    if let Some(proxy) = &network.proxy {
+        let proxy = reqwest::Proxy::all(proxy.as_str())
+            .map_err(|err| format!("Invalid proxy `{}`: {}", proxy, err))?
+            .no_proxy(
+                network
+                    .no_proxy
+                    .as_deref()
+                    .and_then(reqwest::NoProxy::from_string),
+            );
+        builder = builder.proxy(proxy);
+    }
+    if let Some(ca_bundle) = &network.ca_bundle {
+        let pem = std::fs::read(ca_bundle)
+            .map_err(|err| format!("Failed to read {}: {}", ca_bundle, err))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|err| format!("Invalid CA bundle {}: {}", ca_bundle, err))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if network.insecure == Some(true) {
+        log::warn!("TLS certificates are not verified.");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+/// 响应是否是限流，GitHub 使用 403 加上 `X-RateLimit-Remaining: 0`。
+pub fn rate_limited(response: &reqwest::blocking::Response) -> bool {
+    response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || (response.status() == reqwest::StatusCode::FORBIDDEN
+            && header_number(response, "x-ratelimit-remaining") == Some(0))
+}
+
+fn header_number(response: &reqwest::blocking::Response, name: &str) -> Option<u64> {
+    response
+        .headers()
+        .get(name)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// 需要重试时返回等待的时间，优先使用 Retry-After 和 X-RateLimit-Reset
+fn retry_delay(
+    response: &reqwest::blocking::Response,
+    idempotent: bool,
+    attempt: u32,
+) -> Option<Duration> {
+    if rate_limited(response) {
+        if let Some(seconds) = header_number(response, "retry-after") {
+            return Some(Duration::from_secs(seconds));
+        }
+        if let Some(reset) = header_number(response, "x-ratelimit-reset") {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+            return Some(Duration::from_secs(reset.saturating_sub(now) + 1));
+        }
+        return Some(backoff(attempt));
+    }
+    // 非幂等的请求可能已经生效，不能重试
+    (idempotent && response.status().is_server_error()).then(|| backoff(attempt))
+}
+
+fn send_once(
+    request: reqwest::blocking::RequestBuilder,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    match remaining() {
+        Some(remaining) => request.timeout(remaining).send(),
+        None => request.send(),
+    }
+}
+
+/// 在剩余的时间预算内发送请求。限流和服务端错误时按指数退避重试，
+/// 重试后仍然失败时返回最后一次的结果。
+pub fn send(
+    request: reqwest::blocking::RequestBuilder,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let built = request.try_clone().and_then(|request| request.build().ok());
+    if let Some(built) = &built {
+        log::trace!("{} {}", built.method(), built.url());
+    }
+    let idempotent = built.as_ref().is_some_and(|built| {
+        matches!(
+            *built.method(),
+            reqwest::Method::GET | reqwest::Method::HEAD
+        )
+    });
+    let mut attempt = 0;
+    loop {
+        // body 是流时无法复制，只能发送一次
+        let retry = match request.try_clone() {
+            Some(retry) if attempt < RETRIES => retry,
+            _ => return send_once(request),
+        };
+        // 连接失败通常是离线，重试只会更慢
+        let response = send_once(retry)?;
+        match retry_delay(&response, idempotent, attempt) {
+            Some(delay) if wait(delay) => {}
+            _ => return Ok(response),
+        }
+        attempt += 1;
+    }
+}
+
+/// 在最多 CONCURRENCY 个线程中对每一项运行 f，结果与输入的顺序相同。
+pub fn parallel<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let count = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new((0..count).map(|_| None).collect::<Vec<Option<R>>>());
+    std::thread::scope(|scope| {
+        for _ in 0..CONCURRENCY.min(count) {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                let (index, item) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                let result = f(item);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_rate_limit_retry() {
+        use std::io::{Read, Write};
+        assert!(gh_retryable(b"gh: Server Error (HTTP 502)"));
+        assert!(gh_retryable(b"You have exceeded a secondary rate limit"));
+        assert!(!gh_retryable(b"gh: Not Found (HTTP 404)"));
+        assert!(
+            gh_error(b"gh: API rate limit exceeded for 1.2.3.4 (HTTP 403)")
+                .contains("GITHUB_TOKEN")
+        );
+
+        // 先返回一次限流，再返回 200
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            for response in [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            ] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        let client = reqwest::blocking::Client::new();
+        let response = send(client.get(format!("http://{}/", addr))).unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().unwrap(), "ok");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_network_proxy() {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let len = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&buf[..len]).to_string()
+        });
+        let network = Network {
+            proxy: Some(proxy),
+            no_proxy: Some("localhost".to_string()),
+            ..Default::default()
+        };
+        let client = build_client(&network).unwrap();
+        let response = client.get("http://tgit.invalid/ping").send().unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+        // 请求发给了代理，请求行是完整的 URL
+        assert!(server
+            .join()
+            .unwrap()
+            .starts_with("GET http://tgit.invalid/ping HTTP/1.1"));
+
+        let missing = Network {
+            ca_bundle: Some("missing.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(build_client(&missing)
+            .unwrap_err()
+            .to_string()
+            .contains("missing.pem"));
+        let config: Config =
+            toml::from_str("[network]\nproxy = \"http://proxy:8080\"\ninsecure = true\n").unwrap();
+        assert_eq!(config.network.proxy.as_deref(), Some("http://proxy:8080"));
+        assert_eq!(config.network.insecure, Some(true));
+    }
+
+    #[test]
+    fn test_find_program() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        std::fs::write(second.path().join("gh.cmd"), "").unwrap();
+        let paths = std::env::join_paths([first.path(), second.path()]).unwrap();
+        let names = ["gh.exe", "gh.cmd"];
+        assert_eq!(
+            find_program(&paths, &names),
+            Some(second.path().join("gh.cmd"))
+        );
+        std::fs::write(first.path().join("gh.cmd"), "").unwrap();
+        assert_eq!(
+            find_program(&paths, &names),
+            Some(first.path().join("gh.cmd"))
+        );
+        assert_eq!(find_program(&paths, &["gh.bat"]), None);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let items = (0..20).collect::<Vec<u64>>();
+        let results = parallel(items, |item| {
+            std::thread::sleep(std::time::Duration::from_millis(20 - item));
+            item * 2
+        });
+        assert_eq!(results, (0..20).map(|item| item * 2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,67 @@
+use std::io::{IsTerminal, Write};
+
+/// 和 git 一样，less 在内容不足一屏时直接退出，并保留颜色
+const LESS: &str = "FRX";
+
+/// 要使用的 pager 命令。configured 来自 tgit.toml 的 `pager`，其次是 TGIT_PAGER 和 PAGER，默认 less。
+/// 为空或是 `cat` 时不使用 pager。
+pub fn command(configured: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let pager = configured
+        .map(|pager| pager.to_string())
+        .or_else(|| env("TGIT_PAGER"))
+        .or_else(|| env("PAGER"))
+        .unwrap_or("less".to_string());
+    let pager = pager.trim();
+    (!pager.is_empty() && pager != "cat").then(|| pager.to_string())
+}
+
+/// stdout 是终端时通过 pager 输出，否则或者 pager 无法启动时直接打印。
+pub fn page(content: &str, configured: Option<&str>) {
+    if !std::io::stdout().is_terminal() {
+        println!("{}", content);
+        return;
+    }
+    let pager = match command(configured, |name| std::env::var(name).ok()) {
+        Some(pager) => pager,
+        None => {
+            println!("{}", content);
+            return;
+        }
+    };
+    if let Err(err) = spawn(pager.as_str(), content) {
+        log::debug!("failed to run the pager `{}`: {}", pager, err);
+        println!("{}", content);
+    }
+}
+
+fn spawn(pager: &str, content: &str) -> std::io::Result<()> {
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+    let mut command = std::process::Command::new(program);
+    command.args(words).stdin(std::process::Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", LESS);
+    }
+    let mut child = command.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // 用户提前退出 pager 时写入会失败，忽略
+        let _ = writeln!(stdin, "{}", content);
+    }
+    let _ = child.wait();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command() {
+        let env = |name: &str| (name == "PAGER").then(|| "more".to_string());
+        assert_eq!(command(None, env), Some("more".to_string()));
+        assert_eq!(command(Some("less -S"), env), Some("less -S".to_string()));
+        assert_eq!(command(Some(""), env), None);
+        assert_eq!(command(None, |_| None), Some("less".to_string()));
+        assert_eq!(command(None, |_| Some("cat".to_string())), None);
+    }
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ci, compile_globs,
+    config::{Config, DEFAULT_ALLOW_BRANCHES},
+    exit, get_commit_tag_map, get_from_commit, get_remote, get_remote_info, list_tags,
+    parse_first_line, push_range, resolve_commit, versions, Options,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Ignore,
+    Warn,
+    Error,
+}
+
+/// tgit.toml 中的 `[policy]`，决定发布前各项检查失败时的处理方式。
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// 工作区有未提交或未跟踪的文件。默认 error。
+    pub dirty: Option<Level>,
+    /// 未跟踪的文件是否算作未提交，.gitignore 忽略的文件总是不算。默认 true。
+    pub untracked: Option<bool>,
+    /// 当前分支落后于 upstream。默认 warn。
+    pub behind_remote: Option<Level>,
+    /// 无法解析的 commit 比例超过 `unparsable_ratio`。默认 warn。
+    pub unparsable_commits: Option<Level>,
+    /// 默认 0.5。
+    pub unparsable_ratio: Option<f64>,
+    /// range 中存在没有签名的 commit。默认 ignore。
+    pub unsigned_commits: Option<Level>,
+    /// forge 上 HEAD 的 CI 检查失败或还在进行中。默认 ignore。
+    pub ci_status: Option<Level>,
+    /// `version_files` 中的版本不一致。默认 error。
+    pub versions: Option<Level>,
+}
+
+/// code 是 error 时的退出状态码。
+pub fn enforce(level: Level, code: i32, message: String) -> Result<(), Box<dyn std::error::Error>> {
+    match level {
+        Level::Ignore => Ok(()),
+        Level::Warn => {
+            log::warn!("{}", message);
+            Ok(())
+        }
+        Level::Error => Err(exit::fail(code, message)),
+    }
+}
+
+/// 发布前的检查。
+pub fn preflight(
+    repo: &git2::Repository,
+    args: &Options,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let policy = &config.policy;
+    if !args.force {
+        let allowed = if args.allow_branch.is_empty() {
+            config.allow_branches.clone().unwrap_or(
+                DEFAULT_ALLOW_BRANCHES
+                    .map(|branch| branch.to_string())
+                    .to_vec(),
+            )
+        } else {
+            args.allow_branch.clone()
+        };
+        check_branch(repo, &allowed)?;
+    }
+    let dirty = policy.dirty.unwrap_or(Level::Error);
+    if dirty != Level::Ignore && !args.allow_dirty {
+        if let Some(reason) = get_dirty_reason(repo, policy.untracked.unwrap_or(true))? {
+            enforce(dirty, exit::DIRTY, reason)?;
+        }
+    }
+
+    // 在修改版本之前检查，避免在不一致的版本上继续发布
+    let versions = policy.versions.unwrap_or(Level::Error);
+    if versions != Level::Ignore {
+        if let Some(workdir) = repo.workdir() {
+            if let Some(message) = versions::mismatch(&versions::collect(workdir, config)?) {
+                enforce(versions, exit::POLICY, message)?;
+            }
+        }
+    }
+
+    let behind_remote = policy.behind_remote.unwrap_or(Level::Warn);
+    if behind_remote != Level::Ignore {
+        if let Some(behind) = get_behind_count(repo) {
+            if behind > 0 {
+                enforce(
+                    behind_remote,
+                    exit::POLICY,
+                    format!("The branch is {} commit(s) behind its upstream.", behind),
+                )?;
+            }
+        }
+    }
+
+    let ci_status = policy.ci_status.unwrap_or(Level::Ignore);
+    if ci_status != Level::Ignore && !args.skip_ci_check {
+        if let Some(info) = get_remote_info(repo, &get_remote(repo, args)) {
+            let sha = repo.head()?.peel_to_commit()?.id().to_string();
+            match ci::get_checks(&info.host, &info.scope, &info.repo, &sha) {
+                Ok(checks) => {
+                    if let Some(problem) = checks.problem() {
+                        enforce(
+                            ci_status,
+                            exit::POLICY,
+                            format!("{} Use --skip-ci-check to release anyway.", problem),
+                        )?;
+                    }
+                }
+                Err(err) => log::warn!("{}", err),
+            }
+        }
+    }
+
+    let unparsable_commits = policy.unparsable_commits.unwrap_or(Level::Warn);
+    let unsigned_commits = policy.unsigned_commits.unwrap_or(Level::Ignore);
+    if unparsable_commits == Level::Ignore && unsigned_commits == Level::Ignore {
+        return Ok(());
+    }
+    let tags = list_tags(repo, args, config)?;
+    let (c2t, _) = get_commit_tag_map(repo, &tags);
+    let to_commit = resolve_commit(repo, args.to.as_deref().unwrap_or("HEAD"))?;
+    let from_commit = get_from_commit(repo, args.from.clone(), &to_commit, &c2t)?;
+    let mut revwalk = repo.revwalk()?;
+    push_range(&mut revwalk, from_commit.as_ref(), &to_commit)?;
+    let mut total = 0;
+    let mut unparsable = 0;
+    let mut unsigned = 0;
+    for id in revwalk {
+        let id = id?;
+        let commit = repo.find_commit(id)?;
+        total += 1;
+        if parse_first_line(commit.summary().unwrap_or("")).is_none() {
+            unparsable += 1;
+        }
+        if repo.extract_signature(&id, None).is_err() {
+            unsigned += 1;
+        }
+    }
+    let ratio = policy.unparsable_ratio.unwrap_or(0.5);
+    if total > 0 && unparsable as f64 / total as f64 > ratio {
+        enforce(
+            unparsable_commits,
+            exit::POLICY,
+            format!(
+                "{} of {} commit(s) do not follow the conventional commit format.",
+                unparsable, total
+            ),
+        )?;
+    }
+    if unsigned > 0 {
+        enforce(
+            unsigned_commits,
+            exit::POLICY,
+            format!("{} of {} commit(s) are not signed.", unsigned, total),
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_dirty_reason(
+    repo: &git2::Repository,
+    untracked: bool,
+) -> Result<Option<String>, git2::Error> {
+    if repo.state() != git2::RepositoryState::Clean {
+        return Ok(Some("The repository is not clean.".to_string()));
+    }
+    let mut options = git2::StatusOptions::new();
+    options
+        .include_untracked(untracked)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    let statuses = repo.statuses(Some(&mut options))?;
+    let mut changed = Vec::<String>::new();
+    let mut new = Vec::<String>::new();
+    for entry in statuses.iter() {
+        let status = entry.status();
+        let path = entry.path().unwrap_or_default().to_string();
+        if status == git2::Status::WT_NEW {
+            new.push(path);
+        } else if !status.is_ignored() {
+            changed.push(path);
+        }
+    }
+    let mut reasons = Vec::<String>::new();
+    if !changed.is_empty() {
+        reasons.push(format!(
+            "The repository has uncommitted changes: {}.",
+            format_files(&changed)
+        ));
+    }
+    if !new.is_empty() {
+        reasons.push(format!(
+            "The repository has untracked files: {}.",
+            format_files(&new)
+        ));
+    }
+    if reasons.is_empty() {
+        return Ok(None);
+    }
+    reasons.push("Commit or stash them, or use --allow-dirty or --autostash.".to_string());
+    Ok(Some(reasons.join(" ")))
+}
+
+// 最多列出 5 个文件
+fn format_files(files: &[String]) -> String {
+    let mut list = files.iter().take(5).cloned().collect::<Vec<_>>().join(", ");
+    if files.len() > 5 {
+        list.push_str(format!(" and {} more", files.len() - 5).as_str());
+    }
+    list
+}
+
+/// 只允许在 allowed 中的分支上发布，并且 upstream 也必须是允许的分支，例如 `main` 不能跟踪 `origin/feature`。
+pub fn check_branch(
+    repo: &git2::Repository,
+    allowed: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let globs = compile_globs(allowed)?;
+    let is_allowed = |name: &str| globs.iter().any(|glob| glob.is_match(name));
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err(exit::fail(
+            exit::POLICY,
+            "HEAD is detached, check out a release branch or use --force.",
+        ));
+    }
+    let name = head.shorthand().unwrap_or_default();
+    if !is_allowed(name) {
+        return Err(exit::fail(
+            exit::POLICY,
+            format!(
+                "Releasing from {} is not allowed (allowed: {}), use --allow-branch or --force.",
+                name,
+                allowed.join(", ")
+            ),
+        ));
+    }
+    let refname = head.name().unwrap_or_default();
+    let upstream = repo
+        .find_branch(name, git2::BranchType::Local)?
+        .upstream()
+        .ok()
+        .and_then(|upstream| upstream.name().ok().flatten().map(|name| name.to_string()));
+    let remote = repo
+        .branch_upstream_remote(refname)
+        .ok()
+        .and_then(|remote| remote.as_str().map(|remote| remote.to_string()));
+    if let (Some(upstream), Some(remote)) = (upstream, remote) {
+        let remote_branch = upstream
+            .strip_prefix(format!("{}/", remote).as_str())
+            .unwrap_or(upstream.as_str());
+        if !is_allowed(remote_branch) {
+            return Err(exit::fail(
+                exit::POLICY,
+                format!(
+                    "{} tracks {}, which is not an allowed release branch, use --force to release anyway.",
+                    name, upstream
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// 只和本地记录的 upstream 比较，不会 fetch
+pub fn get_behind_count(repo: &git2::Repository) -> Option<usize> {
+    let head = repo.head().ok()?;
+    let branch_name = head.shorthand()?;
+    let branch = repo
+        .find_branch(branch_name, git2::BranchType::Local)
+        .ok()?;
+    let upstream = branch.upstream().ok()?;
+    let local = head.target()?;
+    let remote = upstream.get().target()?;
+    let (_, behind) = repo.graph_ahead_behind(local, remote).ok()?;
+    Some(behind)
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Repository;
+
+    use super::*;
+    use crate::gitt_tests::fixture;
+
+    #[test]
+    fn test_enforce_policy() {
+        assert!(enforce(Level::Ignore, exit::DIRTY, "dirty".to_string()).is_ok());
+        assert!(enforce(Level::Warn, exit::DIRTY, "dirty".to_string()).is_ok());
+        assert_eq!(
+            enforce(Level::Error, exit::DIRTY, "dirty".to_string())
+                .unwrap_err()
+                .to_string(),
+            "dirty"
+        );
+    }
+
+    #[test]
+    fn test_check_branch() {
+        let dir = fixture("with_tag");
+        let repo = Repository::open(dir.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("release/1.x", &head, false).unwrap();
+        repo.set_head("refs/heads/release/1.x").unwrap();
+        let allowed = vec!["main".to_string(), "master".to_string()];
+        assert!(check_branch(&repo, &allowed).is_err());
+        let allowed = vec!["release/*".to_string()];
+        assert!(check_branch(&repo, &allowed).is_ok());
+
+        // upstream 不是允许的分支
+        repo.remote("origin", "https://github.com/Jannchie/tgit-rs.git")
+            .unwrap();
+        repo.reference("refs/remotes/origin/feature", head.id(), false, "test")
+            .unwrap();
+        let mut git_config = repo.config().unwrap();
+        git_config
+            .set_str("branch.release/1.x.remote", "origin")
+            .unwrap();
+        git_config
+            .set_str("branch.release/1.x.merge", "refs/heads/feature")
+            .unwrap();
+        assert!(check_branch(&repo, &allowed).is_err());
+
+        repo.set_head_detached(head.id()).unwrap();
+        assert!(check_branch(&repo, &allowed).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+/// release 流程中的阶段。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Preflight,
+    Changelog,
+    Bump,
+    Commit,
+    Tag,
+    Push,
+}
+
+/// 进度事件，供 GUI 或 CI 显示进度，不需要解析 stdout。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    StageStarted {
+        stage: Stage,
+    },
+    StageFinished {
+        stage: Stage,
+        ok: bool,
+    },
+    /// 从 forge 的 API 获取的字节数
+    BytesFetched {
+        bytes: usize,
+    },
+    CommitProcessed {
+        sha: String,
+    },
+}
+
+type Handler = Box<dyn Fn(&Event) + Send + Sync>;
+
+static HANDLER: OnceLock<Handler> = OnceLock::new();
+
+/// 设置接收事件的回调，只能设置一次。
+pub fn set_handler(handler: Handler) {
+    let _ = HANDLER.set(handler);
+}
+
+pub fn emit(event: Event) {
+    if let Some(handler) = HANDLER.get() {
+        handler(&event);
+    }
+}
+
+/// 以 JSON Lines 的格式把事件写到 stderr。
+pub fn json_handler() -> Handler {
+    Box::new(|event| {
+        if let Ok(line) = serde_json::to_string(event) {
+            eprintln!("{}", line);
+        }
+    })
+}
+
+/// 运行一个阶段，前后分别发出 StageStarted 和 StageFinished。
+pub fn stage<T, E>(stage: Stage, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    emit(Event::StageStarted { stage });
+    let result = f();
+    emit(Event::StageFinished {
+        stage,
+        ok: result.is_ok(),
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_event_json() {
+        assert_eq!(
+            serde_json::to_string(&Event::StageFinished {
+                stage: Stage::Changelog,
+                ok: true
+            })
+            .unwrap(),
+            r#"{"event":"stage_finished","stage":"changelog","ok":true}"#
+        );
+        let result: Result<(), &str> = stage(Stage::Push, || Err("rejected"));
+        assert_eq!(result, Err("rejected"));
+    }
+}
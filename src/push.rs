@@ -0,0 +1,252 @@
+use std::path::PathBuf;
+
+use git2::{Cred, CredentialType, RemoteCallbacks};
+
+use crate::{auth, net};
+
+// 按顺序尝试的 token 环境变量
+const TOKEN_VARS: [&str; 3] = ["TGIT_TOKEN", "GITHUB_TOKEN", "GH_TOKEN"];
+const SSH_KEYS: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// push 失败的原因，用于给出修复建议。
+#[derive(Debug)]
+pub enum PushError {
+    /// remote 上的分支有本地没有的 commit。
+    NonFastForward(String),
+    /// remote 上已经存在同名的 tag。
+    TagExists(String),
+    Auth(String),
+    Other(String),
+}
+
+impl std::fmt::Display for PushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushError::NonFastForward(message) | PushError::Auth(message) => {
+                write!(f, "{}", message)
+            }
+            PushError::TagExists(tag) => write!(f, "The tag {} already exists on the remote.", tag),
+            PushError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for PushError {}
+
+impl From<git2::Error> for PushError {
+    fn from(err: git2::Error) -> Self {
+        PushError::Other(err.message().to_string())
+    }
+}
+
+/// 使用 libgit2 将 refspecs 推送到 remote。
+pub fn push(
+    repo: &git2::Repository,
+    remote_name: &str,
+    refspecs: &[String],
+) -> Result<(), PushError> {
+    let mut remote = repo.find_remote(remote_name)?;
+    let url = remote.url().unwrap_or("").to_string();
+    let git_config = repo.config()?;
+    let mut credentials = Credentials::new(&git_config);
+    let mut rejected = Vec::<(String, String)>::new();
+    let result = {
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(|url, username, allowed| credentials.next(url, username, allowed));
+        callbacks.push_update_reference(|reference, status| {
+            if let Some(status) = status {
+                rejected.push((reference.to_string(), status.to_string()));
+            }
+            Ok(())
+        });
+        let network = net::network();
+        if network.insecure == Some(true) {
+            callbacks.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+        }
+        // 没有配置时和 git 一样使用 http.proxy 和代理的环境变量
+        let mut proxy = git2::ProxyOptions::new();
+        match &network.proxy {
+            Some(url) => proxy.url(url),
+            None => proxy.auto(),
+        };
+        let mut options = git2::PushOptions::new();
+        options.remote_callbacks(callbacks);
+        options.proxy_options(proxy);
+        remote.push(refspecs, Some(&mut options))
+    };
+    if let Err(err) = result {
+        if err.code() == git2::ErrorCode::Auth || err.class() == git2::ErrorClass::Ssh {
+            return Err(PushError::Auth(format!(
+                "Authentication failed for {}: {}\nTried: {}.\nStart ssh-agent, configure a git credential helper, run `tgit auth login` or set one of {}.",
+                url,
+                err.message(),
+                credentials.tried.join(", "),
+                TOKEN_VARS.join(", ")
+            )));
+        }
+        if err.code() == git2::ErrorCode::NotFastForward {
+            return Err(PushError::NonFastForward(format!(
+                "Failed to push to {}: {}",
+                url,
+                err.message()
+            )));
+        }
+        return Err(PushError::Other(format!(
+            "Failed to push to {}: {}",
+            url,
+            err.message()
+        )));
+    }
+    for (reference, status) in &rejected {
+        if let Some(tag) = reference.strip_prefix("refs/tags/") {
+            if status.contains("already exists") {
+                return Err(PushError::TagExists(tag.to_string()));
+            }
+        }
+        if status.contains("non-fast-forward") || status.contains("fetch first") {
+            return Err(PushError::NonFastForward(format!(
+                "The remote rejected {}: {}",
+                reference, status
+            )));
+        }
+    }
+    if !rejected.is_empty() {
+        let rejected = rejected
+            .iter()
+            .map(|(reference, status)| format!("{} ({})", reference, status))
+            .collect::<Vec<_>>();
+        return Err(PushError::Other(format!(
+            "The remote rejected {}.",
+            rejected.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// 依次尝试 ssh-agent、~/.ssh 中的 key、token 环境变量、keychain 中的 token 和 git credential helper。
+/// libgit2 在认证失败后会再次调用，所以每种方式只尝试一次。
+struct Credentials<'a> {
+    git_config: &'a git2::Config,
+    ssh_agent: bool,
+    ssh_keys: Vec<PathBuf>,
+    token_vars: Vec<&'static str>,
+    keychain: bool,
+    credential_helper: bool,
+    tried: Vec<String>,
+}
+
+impl<'a> Credentials<'a> {
+    fn new(git_config: &'a git2::Config) -> Self {
+        let ssh_dir = crate::files::home_dir()
+            .map(|home| home.join(".ssh"))
+            .unwrap_or_default();
+        Self {
+            git_config,
+            ssh_agent: true,
+            ssh_keys: SSH_KEYS
+                .iter()
+                .map(|name| ssh_dir.join(name))
+                .filter(|path| path.exists())
+                .collect(),
+            token_vars: TOKEN_VARS.to_vec(),
+            keychain: true,
+            credential_helper: true,
+            tried: Vec::new(),
+        }
+    }
+
+    fn next(
+        &mut self,
+        url: &str,
+        username: Option<&str>,
+        allowed: CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let username = username.unwrap_or("git");
+            if self.ssh_agent {
+                self.ssh_agent = false;
+                self.tried.push("ssh-agent".to_string());
+                return Cred::ssh_key_from_agent(username);
+            }
+            if !self.ssh_keys.is_empty() {
+                let key = self.ssh_keys.remove(0);
+                self.tried.push(key.display().to_string());
+                return Cred::ssh_key(username, None, key.as_path(), None);
+            }
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            while !self.token_vars.is_empty() {
+                let name = self.token_vars.remove(0);
+                if let Ok(token) = std::env::var(name) {
+                    self.tried.push(format!("${}", name));
+                    // GitHub 的 token 可以使用任意用户名
+                    return Cred::userpass_plaintext(username.unwrap_or("x-access-token"), &token);
+                }
+            }
+            if self.keychain {
+                self.keychain = false;
+                let token =
+                    crate::parse_git_url(url).and_then(|info| auth::stored_token(&info.host));
+                if let Some(token) = token {
+                    self.tried.push("keychain".to_string());
+                    return Cred::userpass_plaintext(username.unwrap_or("x-access-token"), &token);
+                }
+            }
+            if self.credential_helper {
+                self.credential_helper = false;
+                self.tried.push("git credential helper".to_string());
+                return Cred::credential_helper(self.git_config, url, username);
+            }
+        }
+        if self.tried.is_empty() && allowed.contains(CredentialType::DEFAULT) {
+            self.tried.push("default credentials".to_string());
+            return Cred::default();
+        }
+        Err(git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Net,
+            "no more credentials to try",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gitt_tests::{commit_file, fixture};
+
+    #[test]
+    fn test_push() {
+        let dir = fixture("with_tag");
+        let repo = git2::Repository::open(dir.path()).unwrap();
+        let remote_dir = tempfile::tempdir().unwrap();
+        let remote_repo = git2::Repository::init_bare(remote_dir.path()).unwrap();
+        repo.remote("origin", remote_dir.path().to_str().unwrap())
+            .unwrap();
+        let refspecs = vec![
+            "refs/heads/main:refs/heads/main".to_string(),
+            "refs/tags/v0.1.0:refs/tags/v0.1.0".to_string(),
+        ];
+        push(&repo, "origin", &refspecs).unwrap();
+        assert_eq!(
+            remote_repo.refname_to_id("refs/heads/main").unwrap(),
+            repo.refname_to_id("refs/heads/main").unwrap()
+        );
+        assert!(remote_repo.find_reference("refs/tags/v0.1.0").is_ok());
+
+        // 本地的分支不包含 remote 上的 commit
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reset(
+            head.parent(0).unwrap().as_object(),
+            git2::ResetType::Hard,
+            None,
+        )
+        .unwrap();
+        commit_file(&repo, "c.txt", "fix: other");
+        let err = push(&repo, "origin", &refspecs[..1]).unwrap_err();
+        assert!(matches!(err, PushError::NonFastForward(_)));
+    }
+}
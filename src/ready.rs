@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config, exit, get_commit_tag_map, get_from_commit, get_unreleased_commits, list_tags,
+    net, open_repo, resolve_commit, Options,
+};
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// tgit.toml 中的 `[ready]`，决定未发布的 commit 什么时候需要发布。
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    /// 有未发布的 breaking change。默认 true。
+    pub breaking: Option<bool>,
+    /// 最早的未发布 fix 超过的天数。默认 7。
+    pub fix_age_days: Option<u64>,
+    /// 最早的未发布 feat 超过的天数。默认 14。
+    pub feat_age_days: Option<u64>,
+    /// 未发布的 conventional commit 达到的数量。默认不检查。
+    pub commits: Option<usize>,
+    /// 每种类型未发布的 commit 达到的数量，任意一种达到即需要发布，例如 `{ fix = 1, feat = 3 }`。
+    pub pending: Option<BTreeMap<String, usize>>,
+}
+
+/// 检查未发布的 commit 是否需要发布，需要时以 RELEASE_DUE 状态退出。
+/// watch 时每隔一段时间检查一次，直到需要发布。
+pub fn ready(
+    args: &Options,
+    webhook: Option<&str>,
+    watch: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(args.path.as_path())?;
+    loop {
+        let now = chrono::Utc::now().timestamp();
+        let reasons = check(args, &config, now)?;
+        if !reasons.is_empty() {
+            for reason in &reasons {
+                println!("- {}", reason);
+            }
+            if let Some(webhook) = webhook {
+                notify(args, webhook, &reasons)?;
+            }
+            return Err(exit::fail(
+                exit::RELEASE_DUE,
+                "The unreleased commits warrant a release.",
+            ));
+        }
+        let minutes = match watch {
+            Some(minutes) => minutes,
+            None => {
+                println!("No release is needed yet.");
+                return Ok(());
+            }
+        };
+        std::thread::sleep(std::time::Duration::from_secs(minutes.max(1) * 60));
+    }
+}
+
+/// 达到的阈值，没有达到时为空。
+pub fn check(
+    args: &Options,
+    config: &Config,
+    now: i64,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let thresholds = &config.ready;
+    let repo = open_repo(args.path.as_path())?;
+    let tags = list_tags(&repo, args, config)?;
+    let (c2t, _) = get_commit_tag_map(&repo, &tags);
+    let to_commit = resolve_commit(&repo, args.to.as_deref().unwrap_or("HEAD"))?;
+    let from_commit = get_from_commit(&repo, args.from.clone(), &to_commit, &c2t)?;
+    let (commit_map, times) =
+        get_unreleased_commits(&repo, from_commit.as_ref(), &to_commit, args, config)?;
+
+    let mut count = 0;
+    let mut counts = HashMap::<String, usize>::new();
+    let mut breaking = 0;
+    let mut oldest_fix: Option<i64> = None;
+    let mut oldest_feat: Option<i64> = None;
+    for commit in commit_map.values().flatten() {
+        count += 1;
+        *counts.entry(commit.type_.clone()).or_default() += 1;
+        if commit.is_breaking {
+            breaking += 1;
+        }
+        let oldest = match commit.type_.as_str() {
+            "fix" => &mut oldest_fix,
+            "feat" => &mut oldest_feat,
+            _ => continue,
+        };
+        // 合并的相同 commit 取最早的时间
+        let time = std::iter::once(&commit.hash)
+            .chain(&commit.duplicates)
+            .filter_map(|hash| times.get(hash).copied())
+            .min();
+        if let Some(time) = time {
+            *oldest = Some(oldest.map_or(time, |oldest| oldest.min(time)));
+        }
+    }
+
+    let mut reasons = Vec::<String>::new();
+    if thresholds.breaking.unwrap_or(true) && breaking > 0 {
+        reasons.push(format!("{} unreleased breaking change(s).", breaking));
+    }
+    let ages = [
+        ("fix", oldest_fix, thresholds.fix_age_days.unwrap_or(7)),
+        ("feat", oldest_feat, thresholds.feat_age_days.unwrap_or(14)),
+    ];
+    for (type_, oldest, max_days) in ages {
+        let days = match oldest {
+            Some(oldest) => (now - oldest) / DAY,
+            None => continue,
+        };
+        if days >= max_days as i64 {
+            reasons.push(format!(
+                "The oldest unreleased {} is {} days old (threshold {}).",
+                type_, days, max_days
+            ));
+        }
+    }
+    if let Some(max_commits) = thresholds.commits {
+        if count >= max_commits {
+            reasons.push(format!(
+                "{} unreleased commits (threshold {}).",
+                count, max_commits
+            ));
+        }
+    }
+    for (type_, min) in thresholds.pending.iter().flatten() {
+        let pending = counts.get(type_).copied().unwrap_or(0);
+        if pending >= *min {
+            reasons.push(format!(
+                "{} unreleased {} commit(s) (threshold {}).",
+                pending, type_, min
+            ));
+        }
+    }
+    Ok(reasons)
+}
+
+// 以 JSON 发送到 webhook，例如 Slack 的 incoming webhook
+fn notify(
+    args: &Options,
+    webhook: &str,
+    reasons: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::fs::canonicalize(args.path.as_path()).unwrap_or(args.path.clone());
+    let text = format!(
+        "{} is ready for a release:\n{}",
+        path.display(),
+        reasons
+            .iter()
+            .map(|reason| format!("- {}", reason))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    let body = serde_json::json!({
+        "text": text,
+        "repository": path.display().to_string(),
+        "reasons": reasons,
+    });
+    let request = net::client()?
+        .post(webhook)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    let response =
+        net::send(request).map_err(|err| format!("Failed to call the webhook: {}", err))?;
+    if !response.status().is_success() {
+        return Err(format!("The webhook responded with HTTP {}.", response.status()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Repository;
+
+    use super::*;
+    use crate::gitt_tests::{commit_file, fixture, options};
+
+    #[test]
+    fn test_ready() {
+        let dir = fixture("with_tag");
+        let repo = Repository::open(dir.path()).unwrap();
+        let id = commit_file(&repo, "b.txt", "fix: handle empty input");
+        let time = repo.find_commit(id).unwrap().time().seconds();
+        let args = options(&dir, "v");
+        let config = Config::default();
+        assert!(check(&args, &config, time + 24 * 60 * 60)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            check(&args, &config, time + 8 * 24 * 60 * 60).unwrap(),
+            vec!["The oldest unreleased fix is 8 days old (threshold 7).".to_string()]
+        );
+        let mut config = Config::default();
+        config.ready.pending = Some(std::collections::BTreeMap::from([
+            ("feat".to_string(), 3),
+            ("fix".to_string(), 1),
+        ]));
+        assert_eq!(
+            check(&args, &config, time).unwrap(),
+            vec!["2 unreleased fix commit(s) (threshold 1).".to_string()]
+        );
+        // 和 changelog 一样经过 --exclude-commit 和过滤器
+        let mut args = options(&dir, "v");
+        args.exclude_commit = vec![id.to_string()];
+        assert_eq!(
+            check(&args, &config, time).unwrap(),
+            vec!["1 unreleased fix commit(s) (threshold 1).".to_string()]
+        );
+        let config = Config {
+            exclude_types: Some(vec!["fix".to_string()]),
+            ..Config::default()
+        };
+        assert!(check(&options(&dir, "v"), &config, time + 8 * 24 * 60 * 60)
+            .unwrap()
+            .is_empty());
+    }
+}
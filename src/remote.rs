@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    collect_contributors,
+    config::Config,
+    filter::{is_bot, CommitFilter},
+    forge::{self, Forge},
+    get_changelog_string, get_changelog_style, is_excluded, net, parse_git_url, parse_tag_version,
+    resolve_usernames, Author, Commit, Options,
+};
+
+/// 只通过 GitHub API 生成远程仓库 from..to 的 changelog，不需要 clone。
+pub fn remote_changelog(args: &Options, url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let info = parse_git_url(url).ok_or(format!("`{}` is not a repository URL.", url))?;
+    let (host, scope, repo) = (info.host.as_str(), info.scope.as_str(), info.repo.as_str());
+    if !forge::is_github(host) {
+        return Err("Only GitHub repositories are supported.".into());
+    }
+    let prefix = args.prefix.clone().unwrap_or("v".to_string());
+    let from = match &args.from {
+        Some(from) => from.clone(),
+        None => get_latest_tag(host, scope, repo, prefix.as_str())?
+            .ok_or("The repository has no version tags, use --from.")?,
+    };
+    let to = match &args.to {
+        Some(to) => to.clone(),
+        None => gh_api(host, format!("repos/{}/{}", scope, repo).as_str())?["default_branch"]
+            .as_str()
+            .ok_or("Failed to get the default branch.")?
+            .to_string(),
+    };
+
+    let github = forge::GitHub { info: info.clone() };
+    let mut mail_to_login = HashMap::<String, String>::new();
+    let fetched = github
+        .fetch_commits(Some(from.as_str()), to.as_str(), &mut mail_to_login)?
+        .ok_or("Failed to fetch the commits from GitHub.")?;
+    let mut commit_map = HashMap::<String, Vec<Commit>>::new();
+    for fetched in fetched.into_iter().rev() {
+        if is_excluded(fetched.sha.as_str(), &args.exclude_commit) {
+            continue;
+        }
+        let commit = match fetched.parsed {
+            forge::Parsed::Conventional(commit, _) => commit,
+            forge::Parsed::Uncategorized(_) => continue,
+        };
+        commit_map
+            .entry(commit.type_.clone())
+            .or_default()
+            .push(commit);
+    }
+    resolve_usernames(&mut commit_map, &mut mail_to_login, &github);
+    let mut contributors = HashMap::<String, Author>::new();
+    collect_contributors(&commit_map, &mail_to_login, &mut contributors);
+    let has_breaking =
+        CommitFilter::new(args, &Config::default())?.apply(&mut commit_map, &mut contributors);
+    if !args.include_bots {
+        contributors.retain(|_, author| !is_bot(author));
+    }
+
+    // 没有指定 to 时，按照默认的规则推断下一个版本
+    let to_name = if args.to.is_some() {
+        to.clone()
+    } else {
+        let mut version = parse_tag_version(from.as_str(), prefix.as_str());
+        version.pre = semver::Prerelease::EMPTY;
+        if has_breaking {
+            version = semver::Version::new(version.major + 1, 0, 0);
+        } else if commit_map.contains_key("feat") {
+            version = semver::Version::new(version.major, version.minor + 1, 0);
+        } else {
+            version.patch += 1;
+        }
+        format!("{}{}", prefix, version)
+    };
+    let style = get_changelog_style(args, &Config::default())?;
+    let changelog =
+        get_changelog_string(&github, from, to_name, to, commit_map, contributors, &style);
+    println!("{}", changelog);
+    Ok(())
+}
+
+fn gh_api(host: &str, path: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let output = net::gh_output(forge::gh(host).arg("api").arg(path))?
+        .ok_or("Network timeout exceeded while calling the GitHub API.")?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to call the GitHub API: {}",
+            net::gh_error(&output.stderr)
+        )
+        .into());
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+// 以 prefix 开头的 tag 中版本号最大的一个
+fn get_latest_tag(
+    host: &str,
+    scope: &str,
+    repo: &str,
+    prefix: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut latest: Option<(semver::Version, String)> = None;
+    for page in 1.. {
+        let data = gh_api(
+            host,
+            format!("repos/{}/{}/tags?per_page=100&page={}", scope, repo, page).as_str(),
+        )?;
+        let tags = data.as_array().ok_or("Unexpected response from GitHub.")?;
+        for tag in tags {
+            let name = match tag["name"].as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let version = match name
+                .strip_prefix(prefix)
+                .and_then(|version| semver::Version::parse(version).ok())
+            {
+                Some(version) => version,
+                None => continue,
+            };
+            if latest.as_ref().is_none_or(|(latest, _)| version > *latest) {
+                latest = Some((version, name.to_string()));
+            }
+        }
+        if tags.len() < 100 {
+            break;
+        }
+    }
+    Ok(latest.map(|(_, name)| name))
+}
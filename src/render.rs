@@ -0,0 +1,264 @@
+use colored::Colorize;
+
+/// 输出 changelog 的格式。changelog 总是先生成为 markdown，其他格式由 markdown 转换。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    /// 没有 markdown 语法，链接展开为 URL，用于邮件或聊天
+    Plain,
+    Html,
+    /// 带颜色的终端输出，不能通过 --format 选择，打印到终端时自动使用
+    Terminal,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match name {
+            "markdown" => Ok(Format::Markdown),
+            "plain" => Ok(Format::Plain),
+            "html" => Ok(Format::Html),
+            name => Err(format!(
+                "Unknown format `{}`, expected one of: markdown, plain, html.",
+                name
+            )
+            .into()),
+        }
+    }
+}
+
+/// 转换 tgit 生成的 markdown。只支持 changelog 中用到的语法：标题、列表、粗体、删除线、行内代码和链接。
+pub fn render(markdown: &str, format: Format) -> String {
+    match format {
+        Format::Markdown => markdown.to_string(),
+        Format::Plain => render_plain(markdown),
+        Format::Html => render_html(markdown),
+        Format::Terminal => render_terminal(markdown),
+    }
+}
+
+/// `--color` 的取值，auto 时由 colored 根据 NO_COLOR、CLICOLOR 和 stdout 是否是终端判断。
+pub fn set_color(color: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match color {
+        "auto" => colored::control::unset_override(),
+        "always" => colored::control::set_override(true),
+        "never" => colored::control::set_override(false),
+        color => {
+            return Err(format!(
+                "Unknown color `{}`, expected one of: auto, always, never.",
+                color
+            )
+            .into())
+        }
+    }
+    Ok(())
+}
+
+/// 打印到 stdout 的 markdown，需要颜色时转换为终端格式，否则原样输出以便重定向到文件。
+pub fn for_terminal(markdown: &str) -> String {
+    if colored::control::SHOULD_COLORIZE.should_colorize() {
+        render(markdown, Format::Terminal)
+    } else {
+        render(markdown, Format::Markdown)
+    }
+}
+
+fn render_plain(markdown: &str) -> String {
+    let mut lines = Vec::<String>::new();
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("<!--") {
+            continue;
+        }
+        let line = if let Some(title) = trimmed.strip_prefix("## ") {
+            let title = plain_inline(title);
+            format!("{}\n{}", title, "=".repeat(title.chars().count()))
+        } else if let Some(title) = trimmed.strip_prefix("### ") {
+            let title = plain_inline(strip_emoji(title));
+            format!("{}\n{}", title, "-".repeat(title.chars().count()))
+        } else if let Some(scope) = bold_line(trimmed) {
+            format!("{}:", scope)
+        } else {
+            plain_inline(line)
+        };
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+// 版本和类型标题加粗，scope 青色，hash 和 PR 黄色，作者绿色
+fn render_terminal(markdown: &str) -> String {
+    let mut lines = Vec::<String>::new();
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("<!--") {
+            continue;
+        }
+        let line = if let Some(title) = trimmed.strip_prefix("## ") {
+            plain_inline(title).bold().underline().to_string()
+        } else if let Some(title) = trimmed.strip_prefix("### ") {
+            plain_inline(strip_emoji(title))
+                .bold()
+                .magenta()
+                .to_string()
+        } else if let Some(scope) = bold_line(trimmed) {
+            format!("{}:", scope).cyan().bold().to_string()
+        } else if let Some(item) = line.strip_prefix("- ") {
+            format!("- {}", terminal_item(item))
+        } else {
+            terminal_inline(line)
+        };
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+// 条目的最后是 `by 作者` 和 hash，作者中的链接只保留名字
+fn terminal_item(item: &str) -> String {
+    let (description, tail) = match item.rfind(" by ") {
+        Some(index) => (&item[..index], &item[index + 4..]),
+        None => return terminal_inline(item),
+    };
+    let (authors, rest) = match tail.find(" (") {
+        Some(index) => tail.split_at(index),
+        None => (tail, ""),
+    };
+    let authors = regex!(r"\[([^\]]*)\]\(([^)\s]+)\)").replace_all(authors, "$1");
+    format!(
+        "{} by {}{}",
+        terminal_inline(description),
+        authors.green(),
+        terminal_inline(rest)
+    )
+}
+
+fn terminal_inline(text: &str) -> String {
+    let text =
+        regex!(r"\[([^\]]*)\]\(([^)\s]+)\)").replace_all(text, |captures: &regex::Captures| {
+            let label = &captures[1];
+            let is_hash = label.len() == 7 && label.chars().all(|c| c.is_ascii_hexdigit());
+            if is_hash || label.starts_with('#') {
+                label.yellow().to_string()
+            } else if label.starts_with('@') {
+                label.green().to_string()
+            } else {
+                label.underline().to_string()
+            }
+        });
+    let text = regex!(r"\*\*([^*]+)\*\*").replace_all(&text, |captures: &regex::Captures| {
+        captures[1].cyan().bold().to_string()
+    });
+    let text = regex!(r"~~([^~]+)~~").replace_all(&text, |captures: &regex::Captures| {
+        captures[1].strikethrough().to_string()
+    });
+    regex!(r"`([^`]+)`")
+        .replace_all(&text, |captures: &regex::Captures| {
+            captures[1].red().to_string()
+        })
+        .to_string()
+}
+
+fn render_html(markdown: &str) -> String {
+    let mut html = Vec::<String>::new();
+    let mut in_list = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        let item = trimmed.strip_prefix("- ");
+        if in_list && item.is_none() {
+            html.push("</ul>".to_string());
+            in_list = false;
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed.starts_with("<!--") {
+            html.push(trimmed.to_string());
+        } else if let Some(item) = item {
+            if !in_list {
+                html.push("<ul>".to_string());
+                in_list = true;
+            }
+            html.push(format!("<li>{}</li>", html_inline(item)));
+        } else if let Some(title) = trimmed.strip_prefix("## ") {
+            html.push(format!("<h2>{}</h2>", html_inline(title)));
+        } else if let Some(title) = trimmed.strip_prefix("### ") {
+            html.push(format!("<h3>{}</h3>", html_inline(strip_emoji(title))));
+        } else if let Some(scope) = bold_line(trimmed) {
+            html.push(format!("<h4>{}</h4>", html_inline(scope)));
+        } else {
+            html.push(format!("<p>{}</p>", html_inline(trimmed)));
+        }
+    }
+    if in_list {
+        html.push("</ul>".to_string());
+    }
+    let mut html = html.join("\n");
+    html.push('\n');
+    html
+}
+
+// 分组标题，例如 `**parser**`
+fn bold_line(line: &str) -> Option<&str> {
+    line.strip_prefix("**")?.strip_suffix("**")
+}
+
+// 类型标题前的 emoji，例如 `:sparkles: Features`
+fn strip_emoji(title: &str) -> &str {
+    let re = regex!(r"^:[a-z0-9_+-]+:\s*");
+    match re.find(title) {
+        Some(emoji) => &title[emoji.end()..],
+        None => title,
+    }
+}
+
+fn plain_inline(text: &str) -> String {
+    let text = regex!(r"\[([^\]]*)\]\(([^)\s]+)\)").replace_all(text, "$1 ($2)");
+    let text = regex!(r"\*\*([^*]+)\*\*").replace_all(&text, "$1");
+    let text = regex!(r"~~([^~]+)~~").replace_all(&text, "$1");
+    regex!(r"`([^`]+)`").replace_all(&text, "$1").to_string()
+}
+
+fn html_inline(text: &str) -> String {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+    let text = regex!(r"\[([^\]]*)\]\(([^)\s]+)\)").replace_all(&text, r#"<a href="$2">$1</a>"#);
+    let text = regex!(r"\*\*([^*]+)\*\*").replace_all(&text, "<strong>$1</strong>");
+    let text = regex!(r"~~([^~]+)~~").replace_all(&text, "<del>$1</del>");
+    regex!(r"`([^`]+)`")
+        .replace_all(&text, "<code>$1</code>")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let markdown = "## v0.2.0\n\n[compare changes](https://github.com/a/b/compare/v0.1.0...v0.2.0)\n\n### :sparkles: Features\n\n**parser**\n\n- support `<T>` ([1234567](https://github.com/a/b/commit/1234567)) by @a\n";
+        assert_eq!(
+            render(markdown, Format::Plain),
+            "v0.2.0\n======\n\ncompare changes (https://github.com/a/b/compare/v0.1.0...v0.2.0)\n\nFeatures\n--------\n\nparser:\n\n- support <T> (1234567 (https://github.com/a/b/commit/1234567)) by @a"
+        );
+        assert_eq!(
+            render(markdown, Format::Html),
+            "<h2>v0.2.0</h2>\n<p><a href=\"https://github.com/a/b/compare/v0.1.0...v0.2.0\">compare changes</a></p>\n<h3>Features</h3>\n<h4>parser</h4>\n<ul>\n<li>support <code>&lt;T&gt;</code> (<a href=\"https://github.com/a/b/commit/1234567\">1234567</a>) by @a</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_terminal() {
+        use colored::Colorize;
+        colored::control::set_override(true);
+        let markdown = "## v1.0.0\n\n### :sparkles: Features\n\n- **parser** add tables ([#3](https://x/pull/3)) ([abc1234](https://x/commit/abc1234)) - by [@alice](https://github.com/alice)";
+        let rendered = render(markdown, Format::Terminal);
+        assert!(!rendered.contains("](https://"));
+        assert!(!rendered.contains(":sparkles:"));
+        assert!(rendered.contains(&"abc1234".yellow().to_string()));
+        assert!(rendered.contains(&"@alice".green().to_string()));
+        assert!(rendered.contains(&"parser".cyan().bold().to_string()));
+        assert!(set_color("sometimes").is_err());
+    }
+}
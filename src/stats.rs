@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    config::Config, get_commit, get_commit_tag_map, get_from_commit, list_tags, open_repo,
+    push_range, resolve_commit, Options,
+};
+
+const DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// 范围内 commit 的统计。
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Stats {
+    pub commits: usize,
+    /// 无法解析为 conventional commit 的 commit
+    pub unparsable: usize,
+    pub breaking: usize,
+    /// 最早和最晚的 commit 之间的天数
+    pub days: f64,
+    /// 平均每天的 commit 数量
+    pub commits_per_day: f64,
+    pub types: BTreeMap<String, usize>,
+    pub scopes: BTreeMap<String, usize>,
+    /// key 是作者的名字，包括 Co-authored-by 中的作者
+    pub authors: BTreeMap<String, usize>,
+}
+
+pub fn stats(args: &Options, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(args.path.as_path())?;
+    let stats = collect(args, &config)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    println!(
+        "{} commits ({} breaking, {} unparsable) over {:.1} days, {:.2} per day",
+        stats.commits, stats.breaking, stats.unparsable, stats.days, stats.commits_per_day
+    );
+    for (title, counts) in [
+        ("Types", &stats.types),
+        ("Scopes", &stats.scopes),
+        ("Authors", &stats.authors),
+    ] {
+        if counts.is_empty() {
+            continue;
+        }
+        println!("\n{}:", title);
+        print_table(counts, |_| "");
+    }
+    Ok(())
+}
+
+pub fn collect(args: &Options, config: &Config) -> Result<Stats, Box<dyn std::error::Error>> {
+    let repo = open_repo(args.path.as_path())?;
+    let tags = list_tags(&repo, args, config)?;
+    let (c2t, _) = get_commit_tag_map(&repo, &tags);
+    let to_commit = resolve_commit(&repo, args.to.as_deref().unwrap_or("HEAD"))?;
+    let from_commit = get_from_commit(&repo, args.from.clone(), &to_commit, &c2t)?;
+    let mut revwalk = repo.revwalk()?;
+    push_range(&mut revwalk, from_commit.as_ref(), &to_commit)?;
+
+    let mut stats = Stats::default();
+    let mut first: Option<i64> = None;
+    let mut last: Option<i64> = None;
+    for id in revwalk {
+        let git_commit = repo.find_commit(id?)?;
+        let time = git_commit.time().seconds();
+        first = Some(first.map_or(time, |first| first.min(time)));
+        last = Some(last.map_or(time, |last| last.max(time)));
+        stats.commits += 1;
+        let commit = match get_commit(&git_commit) {
+            Some(commit) => commit,
+            None => {
+                stats.unparsable += 1;
+                continue;
+            }
+        };
+        if commit.is_breaking {
+            stats.breaking += 1;
+        }
+        *stats.types.entry(commit.type_.clone()).or_default() += 1;
+        if !commit.scope.is_empty() {
+            *stats.scopes.entry(commit.scope.clone()).or_default() += 1;
+        }
+        for author in &commit.authors {
+            *stats.authors.entry(author.name.clone()).or_default() += 1;
+        }
+    }
+    if let (Some(first), Some(last)) = (first, last) {
+        stats.days = (last - first) as f64 / DAY;
+        // 不到一天时按一天计算
+        stats.commits_per_day = stats.commits as f64 / stats.days.max(1.0);
+    }
+    Ok(stats)
+}
+
+/// 列出历史中使用过的 scope 和次数，标出不在 allowed_scopes 中的 scope。
+pub fn scopes(args: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(args.path.as_path())?;
+    let counts = count_scopes(args)?;
+    let allowed = config.allowed_scopes.unwrap_or_default();
+    print_table(&counts, |scope| {
+        if !allowed.is_empty() && !allowed.iter().any(|allowed| allowed == scope) {
+            "  (not allowed)"
+        } else {
+            ""
+        }
+    });
+    Ok(())
+}
+
+/// to（默认 HEAD）之前所有 commit 的 scope 和次数。
+pub fn count_scopes(args: &Options) -> Result<BTreeMap<String, usize>, Box<dyn std::error::Error>> {
+    let repo = open_repo(args.path.as_path())?;
+    let to_commit = resolve_commit(&repo, args.to.as_deref().unwrap_or("HEAD"))?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(to_commit.id())?;
+    let mut counts = BTreeMap::<String, usize>::new();
+    for id in revwalk {
+        let commit = match get_commit(&repo.find_commit(id?)?) {
+            Some(commit) => commit,
+            None => continue,
+        };
+        if !commit.scope.is_empty() {
+            *counts.entry(commit.scope).or_default() += 1;
+        }
+    }
+    Ok(counts)
+}
+
+// 按数量从多到少，数量相同时按名字排序，note 是每行末尾的说明
+fn print_table(counts: &BTreeMap<String, usize>, note: impl Fn(&str) -> &'static str) {
+    let mut rows = counts.iter().collect::<Vec<_>>();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (name, count) in rows {
+        println!("  {:<width$}  {}{}", name, count, note(name), width = width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Repository;
+
+    use super::*;
+    use crate::gitt_tests::{commit_file, fixture, options};
+
+    #[test]
+    fn test_stats() {
+        let dir = fixture("with_tag");
+        let repo = Repository::open(dir.path()).unwrap();
+        commit_file(&repo, "c.txt", "feat(parser)!: new syntax");
+        commit_file(&repo, "d.txt", "update readme");
+        let stats = collect(&options(&dir, "v"), &Config::default()).unwrap();
+        assert_eq!(stats.commits, 3);
+        assert_eq!(stats.breaking, 1);
+        assert_eq!(stats.unparsable, 1);
+        assert_eq!(
+            stats.types,
+            std::collections::BTreeMap::from([("feat".to_string(), 1), ("fix".to_string(), 1)])
+        );
+        assert_eq!(stats.scopes["parser"], 1);
+        assert_eq!(stats.authors["tgit"], 2);
+        assert_eq!(stats.commits_per_day, 3.0);
+        let scopes = count_scopes(&options(&dir, "v")).unwrap();
+        assert_eq!(
+            scopes.into_iter().collect::<Vec<_>>(),
+            vec![("parser".to_string(), 1)]
+        );
+    }
+}
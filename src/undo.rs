@@ -0,0 +1,386 @@
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+
+use crate::{
+    commit, config::Config, confirm_step, files, find_existing_tag, get_author, get_commit_tag_map,
+    get_remote, list_tags, open_repo, push, split_changelog_sections, Options,
+};
+
+/// 最近一次发布：HEAD 的历史中最新的版本 tag。
+pub struct LastRelease {
+    pub tag: String,
+    pub commit: git2::Oid,
+    /// tag 指向 tgit 创建的 release commit
+    pub release_commit: bool,
+}
+
+/// 是否是 tag 的 release commit：`release: bump version to <tag>`，可能带有 emoji 和跳过 CI 的标记。
+pub fn is_release_commit(message: &str, tag: &str) -> bool {
+    let subject = message.lines().next().unwrap_or("");
+    let subject = subject.strip_prefix(":bookmark: ").unwrap_or(subject);
+    subject
+        .strip_prefix("release: bump version to ")
+        .is_some_and(|rest| rest.split_whitespace().next() == Some(tag))
+}
+
+pub fn find_last_release(
+    repo: &Repository,
+    args: &Options,
+    config: &Config,
+) -> Result<LastRelease, Box<dyn std::error::Error>> {
+    let tags = list_tags(repo, args, config)?;
+    let (c2t, _) = get_commit_tag_map(repo, &tags);
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    for id in revwalk {
+        let id = id?;
+        if let Some(tag) = c2t.get(id.to_string().as_str()) {
+            let message = repo.find_commit(id)?.message().unwrap_or("").to_string();
+            return Ok(LastRelease {
+                tag: tag.clone(),
+                commit: id,
+                release_commit: is_release_commit(message.as_str(), tag),
+            });
+        }
+    }
+    Err("There is no release in the history of HEAD.".into())
+}
+
+/// commit 是否已经在当前分支的 upstream 上。
+pub fn is_pushed(repo: &Repository, commit: git2::Oid) -> bool {
+    let upstream = repo
+        .head()
+        .ok()
+        .and_then(|head| head.name().map(|name| name.to_string()))
+        .and_then(|name| repo.branch_upstream_name(name.as_str()).ok())
+        .and_then(|name| name.as_str().map(|name| name.to_string()))
+        .and_then(|name| repo.refname_to_id(name.as_str()).ok());
+    upstream.is_some_and(|upstream| {
+        upstream == commit || repo.graph_descendant_of(upstream, commit).unwrap_or(false)
+    })
+}
+
+// HEAD 上的 release commit 修改的文件
+fn release_commit_paths(repo: &Repository) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let head = repo.head()?.peel_to_commit()?;
+    let parent = head.parent(0)?;
+    let diff = repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&head.tree()?), None)?;
+    Ok(diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .map(Path::to_path_buf)
+        .collect())
+}
+
+/// 能否丢弃 HEAD 上的 release commit：它修改的文件中未提交的修改会被覆盖。
+pub fn check_drop(repo: &Repository) -> Result<(), Box<dyn std::error::Error>> {
+    for path in release_commit_paths(repo)? {
+        let status = repo.status_file(&path).unwrap_or(git2::Status::CURRENT);
+        if !status.is_empty() && !status.is_ignored() {
+            return Err(format!(
+                "{} has uncommitted changes, commit or stash them first.",
+                path.display()
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// 丢弃 HEAD 上的 release commit。它修改的文件恢复为之前的内容，其他未提交的修改保留。
+pub fn drop_release_commit(repo: &Repository) -> Result<(), Box<dyn std::error::Error>> {
+    check_drop(repo)?;
+    let parent = repo.head()?.peel_to_commit()?.parent(0)?;
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force();
+    for path in release_commit_paths(repo)? {
+        checkout.path(path);
+    }
+    repo.checkout_tree(parent.as_object(), Some(&mut checkout))?;
+    repo.reset(parent.as_object(), git2::ResetType::Soft, None)?;
+    Ok(())
+}
+
+/// changelog 中是否有 tag 的部分。
+pub fn has_section(changelog: &str, tag: &str) -> bool {
+    let (_, sections) = split_changelog_sections(changelog);
+    sections
+        .iter()
+        .any(|(title, _)| title.split_whitespace().next() == Some(tag))
+}
+
+/// 去掉 changelog 中 tag 的部分，没有这个部分时返回 None。
+pub fn remove_section(changelog: &str, tag: &str) -> Option<String> {
+    let (intro, sections) = split_changelog_sections(changelog);
+    let is_tag = |title: &str| title.split_whitespace().next() == Some(tag);
+    if !sections.iter().any(|(title, _)| is_tag(title)) {
+        return None;
+    }
+    let mut content = intro;
+    for (title, text) in sections {
+        if !is_tag(title.as_str()) {
+            content.push_str(text.as_str());
+        }
+    }
+    Some(format!("{}\n", content.trim_end()))
+}
+
+// 冲突时需要重置工作区，所以不能有未提交的修改
+fn check_revert(repo: &Repository) -> Result<(), Box<dyn std::error::Error>> {
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(false);
+    if !repo.statuses(Some(&mut options))?.is_empty() {
+        return Err("Commit or stash the changes before reverting the release commit.".into());
+    }
+    Ok(())
+}
+
+/// 创建撤销 commit 的 commit，标题和 `git revert` 相同，同时去掉 changelog 中 tag 的部分。
+fn revert_commit(
+    repo: &Repository,
+    commit: &git2::Commit,
+    changelog: &Path,
+    tag: &str,
+    author: Option<&str>,
+) -> Result<git2::Oid, Box<dyn std::error::Error>> {
+    check_revert(repo)?;
+    repo.revert(commit, None)?;
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        repo.reset(
+            repo.head()?.peel_to_commit()?.as_object(),
+            git2::ResetType::Hard,
+            None,
+        )?;
+        repo.cleanup_state()?;
+        return Err("Reverting the release commit conflicts, revert it manually.".into());
+    }
+    let workdir = repo.workdir().ok_or("The repository is bare.")?;
+    if let Ok((content, ending)) = files::read(&workdir.join(changelog)) {
+        if let Some(content) = remove_section(content.as_str(), tag) {
+            files::write(&workdir.join(changelog), content.as_str(), ending)?;
+            index.add_path(changelog)?;
+            index.write()?;
+        }
+    }
+    let message = format!(
+        "Revert \"{}\"\n\nThis reverts commit {}.\n",
+        commit.summary().unwrap_or(""),
+        commit.id()
+    );
+    let id = commit::create_commit(repo, &mut index, message.as_str(), author)?;
+    repo.cleanup_state()?;
+    Ok(id)
+}
+
+/// `tgit undo`：丢弃或撤销最近一次发布的 release commit，再删除它的 tag（以及 remote 上的 tag），
+/// 并去掉 changelog 文件中这个版本的部分。每一步都会确认。
+pub fn undo(args: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.path.as_path();
+    let config = Config::load(path)?;
+    let repo = open_repo(path)?;
+    let last = find_last_release(&repo, args, &config)?;
+    let question = format!("Do you want to undo the release {}?", last.tag);
+    if !confirm_step(args.yes, false, question.as_str())? {
+        return Ok(());
+    }
+
+    // 先检查，丢弃或撤销 release commit 失败时 tag 还在
+    let changelog_file = PathBuf::from(config.changelog_path());
+    let head = repo.head()?.peel_to_commit()?;
+    let drop = last.release_commit && last.commit == head.id() && !is_pushed(&repo, head.id());
+    if drop {
+        check_drop(&repo)?;
+    } else if last.release_commit {
+        check_revert(&repo)?;
+    }
+
+    if drop {
+        if confirm_step(args.yes, false, "Do you want to drop the release commit?")? {
+            drop_release_commit(&repo)?;
+            log::info!("Dropped the release commit {}", head.id());
+        }
+    } else if last.release_commit {
+        // 已经推送或者之后还有 commit 时不改写历史
+        if confirm_step(args.yes, false, "Do you want to revert the release commit?")? {
+            let commit = repo.find_commit(last.commit)?;
+            let id = revert_commit(
+                &repo,
+                &commit,
+                changelog_file.as_path(),
+                last.tag.as_str(),
+                get_author(args, &config).as_deref(),
+            )?;
+            log::info!("Created commit {}", id);
+        }
+    }
+
+    repo.tag_delete(last.tag.as_str())?;
+    log::info!("Deleted tag {}", last.tag);
+    let remote = get_remote(&repo, args);
+    if find_existing_tag(&repo, path, Some(remote.as_str()), last.tag.as_str())?.is_some() {
+        let question = format!("Do you want to delete {} on {}?", last.tag, remote);
+        if confirm_step(args.yes, false, question.as_str())? {
+            push::push(
+                &repo,
+                remote.as_str(),
+                &[format!(":refs/tags/{}", last.tag)],
+            )?;
+            log::info!("Deleted tag {} on {}", last.tag, remote);
+        }
+    }
+
+    // changelog 可能是在 release commit 之前写入的
+    let changelog_path = path.join(changelog_file);
+    if let Ok((content, ending)) = files::read(&changelog_path) {
+        if let Some(content) = remove_section(content.as_str(), last.tag.as_str()) {
+            let question = format!(
+                "Do you want to remove {} from {}?",
+                last.tag,
+                config.changelog_path()
+            );
+            if confirm_step(args.yes, false, question.as_str())? {
+                files::write(&changelog_path, content.as_str(), ending)?;
+                log::info!("Removed {} from {}", last.tag, config.changelog_path());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `tgit release --amend` 要替换的发布。
+pub struct Amend {
+    pub last: LastRelease,
+    /// release commit 在 HEAD 上并且还没有推送，重新发布前丢弃它
+    pub drop: bool,
+    /// changelog 文件中有这个版本，重新发布后需要重新生成
+    pub has_section: bool,
+}
+
+/// `tgit release --amend` 的准备：找到最近一次发布并确认，这时还不修改仓库；取消时返回 None。
+pub fn plan_amend(
+    repo: &Repository,
+    args: &Options,
+    config: &Config,
+    path: &Path,
+) -> Result<Option<Amend>, Box<dyn std::error::Error>> {
+    let last = find_last_release(repo, args, config)?;
+    let question = format!("Do you want to amend the release {}?", last.tag);
+    if !confirm_step(args.yes, false, question.as_str())? {
+        return Ok(None);
+    }
+    let changelog = files::read(&path.join(config.changelog_path()))
+        .map(|(content, _)| content)
+        .unwrap_or_default();
+    let head = repo.head()?.peel_to_commit()?.id();
+    let drop = last.release_commit && last.commit == head && !is_pushed(repo, head);
+    if last.release_commit && last.commit == head && !drop {
+        log::info!("The release commit has been pushed, a new one is created on top of it.");
+    }
+    let has_section = has_section(changelog.as_str(), last.tag.as_str());
+    Ok(Some(Amend {
+        last,
+        drop,
+        has_section,
+    }))
+}
+
+impl Amend {
+    /// 撤销最近一次发布之后的参数和配置：忽略它的 tag，丢弃 release commit 时范围到它的 parent 为止。
+    /// 发布前的检查和 changelog 都使用它们，所以检查失败时最近一次发布不受影响。
+    pub fn view(
+        &self,
+        repo: &Repository,
+        args: &Options,
+        config: &Config,
+    ) -> Result<(Options, Config), Box<dyn std::error::Error>> {
+        let mut args = args.clone();
+        if self.drop && args.to.is_none() {
+            let parent = repo.find_commit(self.last.commit)?.parent_id(0)?;
+            args.to = Some(parent.to_string());
+        }
+        let mut config = config.clone();
+        config
+            .exclude_tags
+            .get_or_insert_with(Vec::new)
+            .push(self.last.tag.clone());
+        Ok((args, config))
+    }
+
+    /// 丢弃 release commit 并删除 tag，在写入新的发布之前调用。
+    pub fn apply(&self, repo: &Repository) -> Result<(), Box<dyn std::error::Error>> {
+        if self.drop {
+            drop_release_commit(repo)?;
+            log::info!("Dropped the release commit {}", self.last.commit);
+        }
+        repo.tag_delete(self.last.tag.as_str())?;
+        log::info!("Deleted tag {}", self.last.tag);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gitt_tests::{commit_file, fixture};
+    use clap::Parser;
+
+    #[test]
+    fn test_undo_release() {
+        assert_eq!(
+            remove_section(
+                "# Changelog\n\n## v0.2.0\n\n- fix\n\n## v0.1.0\n\n- init\n",
+                "v0.2.0"
+            )
+            .as_deref(),
+            Some("# Changelog\n\n## v0.1.0\n\n- init\n")
+        );
+        assert_eq!(remove_section("## v0.1.0\n", "v0.2.0"), None);
+
+        let dir = fixture("with_tag");
+        let repo = Repository::open(dir.path()).unwrap();
+        let commit_changelog = |content: &str, message: &str| {
+            std::fs::write(dir.path().join("CHANGELOG.md"), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index
+                .add_path(std::path::Path::new("CHANGELOG.md"))
+                .unwrap();
+            index.write().unwrap();
+            commit::create_commit(&repo, &mut index, message, Some("tgit <tgit@example.com>"))
+                .unwrap()
+        };
+        let before = "# Changelog\n\n## v0.1.0\n\n- init\n";
+        commit_changelog(before, "docs: add the changelog");
+        let release = commit_changelog(
+            "# Changelog\n\n## v0.2.0\n\n- something\n\n## v0.1.0\n\n- init\n",
+            "release: bump version to v0.2.0",
+        );
+        repo.tag_lightweight("v0.2.0", &repo.find_object(release, None).unwrap(), false)
+            .unwrap();
+        // release commit 之后还有 commit，只能撤销
+        commit_file(&repo, "c.txt", "docs: later");
+        let args = Options::parse_from(vec!["tgit", "-y", dir.path().to_str().unwrap()]);
+        // 有未提交的修改时无法撤销，tag 保留
+        std::fs::write(dir.path().join("a.txt"), "edited").unwrap();
+        let later = repo.head().unwrap().peel_to_commit().unwrap().id();
+        assert!(undo(&args).is_err());
+        assert!(repo.find_reference("refs/tags/v0.2.0").is_ok());
+        assert_eq!(repo.head().unwrap().peel_to_commit().unwrap().id(), later);
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        undo(&args).unwrap();
+        assert!(repo.find_reference("refs/tags/v0.2.0").is_err());
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(
+            head.summary(),
+            Some("Revert \"release: bump version to v0.2.0\"")
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("CHANGELOG.md")).unwrap(),
+            before
+        );
+        assert!(repo.statuses(None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cargo, config::Config, exit, files, Options};
+
+/// 未配置 `version_files` 时检查的文件，不存在的文件会被跳过。
+pub const DEFAULT_FILES: [&str; 3] = ["Cargo.toml", "package.json", "pyproject.toml"];
+
+/// 一个版本文件中找到的版本。
+#[derive(Debug, PartialEq)]
+pub struct Found {
+    pub file: String,
+    pub versions: Vec<String>,
+}
+
+/// 文件中记录的版本。Cargo.toml、package.json 和 pyproject.toml 按格式解析，
+/// 其他文件（例如 README 中的 badge）取 `version` 后面的每一个版本号。
+pub fn read_versions(file: &str, content: &str) -> Vec<String> {
+    let name = Path::new(file)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(file);
+    let version = match name {
+        "Cargo.toml" => cargo::version(content),
+        "package.json" => serde_json::from_str::<serde_json::Value>(content)
+            .ok()
+            .and_then(|json| json.get("version")?.as_str().map(|v| v.to_string())),
+        "pyproject.toml" => toml::from_str::<toml::Value>(content)
+            .ok()
+            .and_then(|toml| {
+                let project = toml
+                    .get("project")
+                    .and_then(|project| project.get("version"));
+                let poetry = || toml.get("tool")?.get("poetry")?.get("version");
+                project
+                    .or_else(poetry)
+                    .and_then(|version| version.as_str())
+                    .map(|version| version.to_string())
+            }),
+        _ => {
+            let mut versions = Vec::new();
+            for captures in regex!(
+                r#"(?i)version[-_ :=/"'@v]*(\d+\.\d+\.\d+(?:-(?:alpha|beta|rc|pre|dev)[.\d]*)?)"#
+            )
+            .captures_iter(content)
+            {
+                let version = captures[1].to_string();
+                if !versions.contains(&version) {
+                    versions.push(version);
+                }
+            }
+            return versions;
+        }
+    };
+    version.into_iter().collect()
+}
+
+/// 读取配置的版本文件。配置的文件不存在时报错，默认的文件不存在或没有版本时跳过。
+pub fn collect(path: &Path, config: &Config) -> Result<Vec<Found>, Box<dyn std::error::Error>> {
+    let configured = config.version_files.is_some();
+    let files = config
+        .version_files
+        .clone()
+        .unwrap_or(DEFAULT_FILES.map(|file| file.to_string()).to_vec());
+    let mut found = Vec::new();
+    for file in files {
+        let content = match std::fs::read_to_string(path.join(file.as_str())) {
+            Ok(content) => content,
+            Err(_) if !configured => continue,
+            Err(err) => return Err(format!("Failed to read {}: {}", file, err).into()),
+        };
+        let versions = read_versions(file.as_str(), content.as_str());
+        if versions.is_empty() && !configured {
+            continue;
+        }
+        found.push(Found { file, versions });
+    }
+    Ok(found)
+}
+
+fn describe(found: &Found) -> String {
+    match found.versions.is_empty() {
+        true => format!("{}: no version found", found.file),
+        false => format!("{}: {}", found.file, found.versions.join(", ")),
+    }
+}
+
+fn agree(found: &[Found]) -> bool {
+    let mut versions = found.iter().flat_map(|found| found.versions.iter());
+    let first = versions.next();
+    found.iter().all(|found| !found.versions.is_empty())
+        && versions.all(|version| Some(version) == first)
+}
+
+/// 版本不一致或配置的文件中没有版本时返回说明。
+pub fn mismatch(found: &[Found]) -> Option<String> {
+    if agree(found) {
+        return None;
+    }
+    let lines = found
+        .iter()
+        .map(|found| format!("  {}", describe(found)))
+        .collect::<Vec<_>>();
+    Some(format!(
+        "The version files do not agree:\n{}",
+        lines.join("\n")
+    ))
+}
+
+/// `tgit check-versions`
+pub fn check_versions(args: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(args.path.as_path())?;
+    let found = collect(args.path.as_path(), &config)?;
+    if found.is_empty() {
+        log::info!("No version files found.");
+    }
+    for found in &found {
+        println!("{}", describe(found));
+    }
+    if !agree(&found) {
+        return Err(exit::fail(exit::POLICY, "The version files do not agree."));
+    }
+    Ok(())
+}
+
+/// tgit.toml 中的 `[[replacements]]`，bump 时更新其他文件中的版本，例如 README 中的安装命令。
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replacement {
+    /// 相对于仓库根目录的路径。
+    pub file: String,
+    /// 要查找的文本，`{version}`、`{major}`、`{minor}` 和 `{patch}` 匹配任意的旧版本，例如 `tgit = "{major}.{minor}"`。
+    pub search: String,
+    /// 替换后的文本，占位符替换为新版本。默认和 search 相同。
+    pub replace: Option<String>,
+}
+
+const PLACEHOLDERS: [&str; 4] = ["version", "major", "minor", "patch"];
+
+/// search 模板对应的正则，占位符以外的部分按原样匹配。
+fn search_regex(search: &str) -> Result<regex::Regex, regex::Error> {
+    let mut pattern = String::new();
+    let mut rest = search;
+    while let Some(start) = rest.find('{') {
+        let placeholder = rest[start + 1..]
+            .split_once('}')
+            .and_then(|(name, _)| PLACEHOLDERS.contains(&name).then_some(name));
+        pattern.push_str(regex::escape(&rest[..start]).as_str());
+        match placeholder {
+            Some(name) => {
+                pattern.push_str(match name {
+                    "version" => r"\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?",
+                    _ => r"\d+",
+                });
+                rest = &rest[start + name.len() + 2..];
+            }
+            None => {
+                pattern.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    pattern.push_str(regex::escape(rest).as_str());
+    regex::Regex::new(pattern.as_str())
+}
+
+/// 把模板中的占位符替换为 version 的对应部分。
+fn render(template: &str, version: &str) -> String {
+    let core = version.split(['-', '+']).next().unwrap_or(version);
+    let mut parts = core.split('.');
+    let mut rendered = template.replace("{version}", version);
+    for name in ["major", "minor", "patch"] {
+        let part = parts.next().unwrap_or("0");
+        rendered = rendered.replace(format!("{{{}}}", name).as_str(), part);
+    }
+    rendered
+}
+
+/// 替换 content 中所有匹配的文本，返回替换后的内容和匹配的次数。
+pub fn replace(
+    content: &str,
+    replacement: &Replacement,
+    version: &str,
+) -> Result<(String, usize), Box<dyn std::error::Error>> {
+    let search = search_regex(replacement.search.as_str())
+        .map_err(|err| format!("Invalid search `{}`: {}", replacement.search, err))?;
+    let replace = render(
+        replacement
+            .replace
+            .as_deref()
+            .unwrap_or(replacement.search.as_str()),
+        version,
+    );
+    let count = search.find_iter(content).count();
+    let content = search.replace_all(content, regex::NoExpand(replace.as_str()));
+    Ok((content.into_owned(), count))
+}
+
+/// 按照 `replacements` 更新文件中的版本，没有匹配的文本时警告。
+pub fn apply_replacements(
+    path: &Path,
+    replacements: &[Replacement],
+    version: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut written = Vec::new();
+    for replacement in replacements {
+        let file = path.join(replacement.file.as_str());
+        let (content, ending) = files::read(file.as_path())
+            .map_err(|err| format!("Failed to read {}: {}", replacement.file, err))?;
+        let (new_content, count) = replace(content.as_str(), replacement, version)?;
+        if count == 0 {
+            log::warn!(
+                "`{}` does not match anything in {}.",
+                replacement.search,
+                replacement.file
+            );
+            continue;
+        }
+        if new_content != content {
+            files::write(file.as_path(), new_content.as_str(), ending)?;
+            written.push(file);
+        }
+        log::debug!("replaced {} occurrence(s) in {}", count, replacement.file);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::update_version;
+
+    #[test]
+    fn test_replacements() {
+        let replacement = |search: &str, replace: Option<&str>| Replacement {
+            file: "README.md".to_string(),
+            search: search.to_string(),
+            replace: replace.map(|replace| replace.to_string()),
+        };
+        let readme = "```toml\ntgit = \"0.3\"\n```\n{not a placeholder}\n";
+        let (content, count) = replace(
+            readme,
+            &replacement("tgit = \"{major}.{minor}\"", None),
+            "0.4.1",
+        )
+        .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(content, readme.replace("0.3", "0.4"));
+        let dockerfile = "ARG VERSION=1.0.0-beta.1\nARG OTHER=1.0.0\n";
+        let (content, _) = replace(
+            dockerfile,
+            &replacement("ARG VERSION={version}", None),
+            "1.0.0",
+        )
+        .unwrap();
+        assert_eq!(content, "ARG VERSION=1.0.0\nARG OTHER=1.0.0\n");
+        let (content, _) = replace(
+            "image: app:v1.2.3\n",
+            &replacement("app:v{version}", Some("app:{version}")),
+            "1.3.0",
+        )
+        .unwrap();
+        assert_eq!(content, "image: app:1.3.0\n");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("README.md"), "tgit = \"0.3\"\r\n").unwrap();
+        let config = Config {
+            replacements: Some(vec![replacement("tgit = \"{major}.{minor}\"", None)]),
+            ..Default::default()
+        };
+        update_version(dir.path(), &config, "v0.4.0", "v").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("README.md")).unwrap(),
+            "tgit = \"0.4\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_version_files() {
+        assert_eq!(
+            read_versions("package.json", r#"{"name": "a", "version": "1.2.0"}"#),
+            vec!["1.2.0"]
+        );
+        assert_eq!(
+            read_versions("pyproject.toml", "[tool.poetry]\nversion = \"1.2.0\"\n"),
+            vec!["1.2.0"]
+        );
+        assert_eq!(
+            read_versions(
+                "README.md",
+                "![version](https://img.shields.io/badge/version-1.2.0-blue)\nserde 1.0.0\n"
+            ),
+            vec!["1.2.0"]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"a\"\nversion = \"1.2.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("package.json"), r#"{"private": true}"#).unwrap();
+        // 默认的文件中没有版本时跳过
+        let found = collect(dir.path(), &Config::default()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(mismatch(&found), None);
+
+        std::fs::write(dir.path().join("package.json"), r#"{"version": "1.1.0"}"#).unwrap();
+        let found = collect(dir.path(), &Config::default()).unwrap();
+        let message = mismatch(&found).unwrap();
+        assert!(message.contains("Cargo.toml: 1.2.0"));
+        assert!(message.contains("package.json: 1.1.0"));
+
+        let config = Config {
+            version_files: Some(vec!["Cargo.toml".to_string(), "VERSION".to_string()]),
+            ..Default::default()
+        };
+        assert!(collect(dir.path(), &config).is_err());
+    }
+}